@@ -0,0 +1,630 @@
+// Babylon BTC staking scripts (btcstaking)
+// Builds staking outputs in the canister: a Taproot tree of timelock, unbonding and slashing
+// leaves under an unspendable internal key, from the staker, finality provider and covenant keys.
+// Also builds the unbonding, withdrawal and slashing transactions and their script-path witnesses.
+use ree_types::bitcoin::{
+    hashes::Hash,
+    opcodes::all::{OP_CHECKSIG, OP_CHECKSIGADD, OP_CHECKSIGVERIFY, OP_CSV, OP_NUMEQUAL, OP_NUMEQUALVERIFY},
+    psbt::Psbt,
+    script::Builder,
+    secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot::{ControlBlock, LeafVersion, TapLeafHash, TaprootBuilder, TaprootSpendInfo},
+    transaction::Version,
+    ScriptBuf, TxOut, Witness,
+};
+
+use crate::psbt_builder::{Outpoint, PsbtBuilder};
+use crate::tx_size::SCHNORR_SIG_LEN;
+
+/// BIP341 NUMS point lift_x(SHA256(G)): no known private key, so only the script paths can spend
+const UNSPENDABLE_KEY_HEX: &str = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+
+/// Parse a BTC public key as BIP340 x-only (32 bytes); 33-byte compressed keys drop their parity byte
+pub fn parse_btc_pk(pk_hex: &str) -> Result<XOnlyPublicKey, String> {
+    let bytes = hex::decode(pk_hex).map_err(|e| format!("Invalid BTC public key hex {}: {}", pk_hex, e))?;
+    let x_only = match bytes.len() {
+        32 => &bytes[..],
+        33 if bytes[0] == 0x02 || bytes[0] == 0x03 => &bytes[1..],
+        len => return Err(format!("BTC public key {} is {} bytes (expected 32 or 33)", pk_hex, len)),
+    };
+    XOnlyPublicKey::from_slice(x_only).map_err(|e| format!("BTC public key {} is not on secp256k1: {}", pk_hex, e))
+}
+
+/// Standard (RFC 4648) base64, as Cosmos REST encodes proto `bytes` fields (e.g. slashing_pk_script)
+pub fn decode_base64(encoded: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in encoded.trim().trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("Invalid base64 character {:?}", c as char)),
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bits >= 6 {
+        return Err(format!("Truncated base64 {}", encoded));
+    }
+    Ok(bytes)
+}
+
+/// BTC keys a staking output commits to
+#[derive(Clone, Debug)]
+pub struct StakingKeys {
+    pub staker: XOnlyPublicKey,
+    pub finality_providers: Vec<XOnlyPublicKey>,
+    pub covenant: Vec<XOnlyPublicKey>,
+    pub covenant_quorum: u32,
+}
+
+impl StakingKeys {
+    pub fn new(
+        staker_pk: &str,
+        finality_provider_pks: &[String],
+        covenant_pks: &[String],
+        covenant_quorum: u32,
+    ) -> Result<Self, String> {
+        let keys = Self {
+            staker: parse_btc_pk(staker_pk)?,
+            finality_providers: finality_provider_pks.iter().map(|pk| parse_btc_pk(pk)).collect::<Result<_, _>>()?,
+            covenant: covenant_pks.iter().map(|pk| parse_btc_pk(pk)).collect::<Result<_, _>>()?,
+            covenant_quorum,
+        };
+
+        if keys.finality_providers.is_empty() {
+            return Err("At least one finality provider key is required".to_string());
+        }
+        if keys.covenant_quorum == 0 || keys.covenant_quorum as usize > keys.covenant.len() {
+            return Err(format!("Covenant quorum {} is invalid for {} covenant keys", keys.covenant_quorum, keys.covenant.len()));
+        }
+        if keys.finality_providers.contains(&keys.staker) || keys.covenant.contains(&keys.staker) {
+            return Err("Staker key must differ from finality provider and covenant keys".to_string());
+        }
+
+        Ok(keys)
+    }
+}
+
+/// `<staker> CHECKSIGVERIFY <lock_time> CHECKSEQUENCEVERIFY`
+fn timelock_script(staker: &XOnlyPublicKey, lock_time: u16) -> ScriptBuf {
+    Builder::new()
+        .push_x_only_key(staker)
+        .push_opcode(OP_CHECKSIGVERIFY)
+        .push_int(lock_time as i64)
+        .push_opcode(OP_CSV)
+        .into_script()
+}
+
+/// `<staker> CHECKSIGVERIFY`, prefix of the unbonding and slashing leaves
+fn staker_sig(builder: Builder, staker: &XOnlyPublicKey) -> Builder {
+    builder.push_x_only_key(staker).push_opcode(OP_CHECKSIGVERIFY)
+}
+
+/// `threshold`-of-n over keys sorted by their serialization (CHECKSIG / CHECKSIGADD / NUMEQUAL);
+/// a single key is a plain signature check
+fn multisig(mut builder: Builder, keys: &[XOnlyPublicKey], threshold: u32, verify: bool) -> Result<Builder, String> {
+    if keys.len() == 1 {
+        let op = if verify { OP_CHECKSIGVERIFY } else { OP_CHECKSIG };
+        return Ok(builder.push_x_only_key(&keys[0]).push_opcode(op));
+    }
+
+    let mut sorted = keys.to_vec();
+    sorted.sort_by_key(|key| key.serialize());
+    if sorted.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("Duplicate key in multisig".to_string());
+    }
+
+    for (i, key) in sorted.iter().enumerate() {
+        let op = if i == 0 { OP_CHECKSIG } else { OP_CHECKSIGADD };
+        builder = builder.push_x_only_key(key).push_opcode(op);
+    }
+    let op = if verify { OP_NUMEQUALVERIFY } else { OP_NUMEQUAL };
+    Ok(builder.push_int(threshold as i64).push_opcode(op))
+}
+
+/// Staker + covenant quorum: early unbonding, co-signed by the covenant committee
+fn unbonding_script(keys: &StakingKeys) -> Result<ScriptBuf, String> {
+    let builder = staker_sig(Builder::new(), &keys.staker);
+    Ok(multisig(builder, &keys.covenant, keys.covenant_quorum, false)?.into_script())
+}
+
+/// Staker + one finality provider + covenant quorum: slashing after a finality provider double-signs
+fn slashing_script(keys: &StakingKeys) -> Result<ScriptBuf, String> {
+    let builder = staker_sig(Builder::new(), &keys.staker);
+    let builder = multisig(builder, &keys.finality_providers, 1, true)?;
+    Ok(multisig(builder, &keys.covenant, keys.covenant_quorum, false)?.into_script())
+}
+
+/// A Babylon Taproot output and the leaves that spend it (unbonding outputs have no unbonding leaf)
+#[derive(Clone, Debug)]
+pub struct BabylonOutput {
+    pub timelock_script: ScriptBuf,
+    pub unbonding_script: Option<ScriptBuf>,
+    pub slashing_script: ScriptBuf,
+    spend_info: TaprootSpendInfo,
+}
+
+impl BabylonOutput {
+    pub fn script_pubkey(&self) -> ScriptBuf {
+        ScriptBuf::new_p2tr_tweaked(self.spend_info.output_key())
+    }
+
+    /// Control block proving `leaf` is in the tree (witness element for a script-path spend)
+    pub fn control_block(&self, leaf: &ScriptBuf) -> Result<ControlBlock, String> {
+        self.spend_info.control_block(&(leaf.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| "Script is not a leaf of this output".to_string())
+    }
+}
+
+fn unspendable_key() -> XOnlyPublicKey {
+    XOnlyPublicKey::from_slice(&hex::decode(UNSPENDABLE_KEY_HEX).expect("valid hex")).expect("valid NUMS key")
+}
+
+/// Staking output for `staking_time` blocks
+///
+/// Same tree as btcstaking's BuildStakingInfo: ((timelock, unbonding), slashing).
+pub fn staking_output(keys: &StakingKeys, staking_time: u16) -> Result<BabylonOutput, String> {
+    let timelock_script = timelock_script(&keys.staker, staking_time);
+    let unbonding_script = unbonding_script(keys)?;
+    let slashing_script = slashing_script(keys)?;
+
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(2, timelock_script.clone())
+        .and_then(|builder| builder.add_leaf(2, unbonding_script.clone()))
+        .and_then(|builder| builder.add_leaf(1, slashing_script.clone()))
+        .map_err(|e| format!("Failed to build staking script tree: {:?}", e))?
+        .finalize(&Secp256k1::verification_only(), unspendable_key())
+        .map_err(|_| "Staking script tree is incomplete".to_string())?;
+
+    Ok(BabylonOutput {
+        timelock_script,
+        unbonding_script: Some(unbonding_script),
+        slashing_script,
+        spend_info,
+    })
+}
+
+/// Unbonding output for `unbonding_time` blocks
+///
+/// Same tree as btcstaking's BuildUnbondingInfo: (timelock, slashing).
+pub fn unbonding_output(keys: &StakingKeys, unbonding_time: u16) -> Result<BabylonOutput, String> {
+    let timelock_script = timelock_script(&keys.staker, unbonding_time);
+    let slashing_script = slashing_script(keys)?;
+
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(1, timelock_script.clone())
+        .and_then(|builder| builder.add_leaf(1, slashing_script.clone()))
+        .map_err(|e| format!("Failed to build unbonding script tree: {:?}", e))?
+        .finalize(&Secp256k1::verification_only(), unspendable_key())
+        .map_err(|_| "Unbonding script tree is incomplete".to_string())?;
+
+    Ok(BabylonOutput {
+        timelock_script,
+        unbonding_script: None,
+        slashing_script,
+        spend_info,
+    })
+}
+
+/// Unbonding transaction: the staking output → the unbonding output, less Babylon's unbonding fee
+///
+/// btcstaking requires exactly one input and one output, and a final (non-replaceable) sequence.
+pub fn unbonding_transaction(
+    staking_outpoint: Outpoint,
+    staking: &BabylonOutput,
+    staking_sats: u64,
+    unbonding: &BabylonOutput,
+    unbonding_fee_sats: u64,
+) -> Result<Psbt, String> {
+    let unbonding_sats = staking_sats.checked_sub(unbonding_fee_sats)
+        .filter(|sats| *sats > 0)
+        .ok_or_else(|| format!("Unbonding fee {} sats exceeds staking amount {} sats", unbonding_fee_sats, staking_sats))?;
+
+    PsbtBuilder::new()
+        .input(staking_outpoint, staking_sats, staking.script_pubkey())
+        .output(unbonding.script_pubkey(), unbonding_sats)
+        .build()
+}
+
+/// btcstaking slashing parameters
+#[derive(Clone, Debug)]
+pub struct SlashingParams {
+    pub slashing_script_pubkey: ScriptBuf,  // Where slashed funds go
+    pub slashing_rate: String,              // Decimal fraction of the output, e.g. "0.1"
+    pub slashing_fee_sats: u64,             // min_slashing_tx_fee_sat
+    pub change_lock_time: u16,              // Staker's change is timelocked for the unbonding time
+}
+
+/// Decimal fraction in (0, 1) as an integer scaled by 10^18 (Cosmos LegacyDec precision)
+pub fn parse_slashing_rate(rate: &str) -> Result<u128, String> {
+    const DECIMALS: usize = 18;
+    let (whole, fraction) = rate.trim().split_once('.').unwrap_or((rate.trim(), ""));
+    if !whole.trim_start_matches('0').is_empty() || fraction.len() > DECIMALS || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid slashing rate {} (expected a decimal between 0 and 1)", rate));
+    }
+    let scaled = format!("{:0<width$}", fraction, width = DECIMALS).parse::<u128>()
+        .map_err(|e| format!("Invalid slashing rate {}: {}", rate, e))?;
+    if scaled == 0 {
+        return Err(format!("Slashing rate {} must be above zero", rate));
+    }
+    Ok(scaled)
+}
+
+/// Taproot output spendable by the staker alone after `lock_time` blocks (slashing change)
+fn relative_timelock_output(staker: &XOnlyPublicKey, lock_time: u16) -> Result<ScriptBuf, String> {
+    let spend_info = TaprootBuilder::new()
+        .add_leaf(0, timelock_script(staker, lock_time))
+        .map_err(|e| format!("Failed to build timelock script tree: {:?}", e))?
+        .finalize(&Secp256k1::verification_only(), unspendable_key())
+        .map_err(|_| "Timelock script tree is incomplete".to_string())?;
+    Ok(ScriptBuf::new_p2tr_tweaked(spend_info.output_key()))
+}
+
+/// Slashing transaction for a staking or unbonding output (spent through its slashing leaf)
+///
+/// Same layout as btcstaking's BuildSlashingTxFromOutpoint: a version-1 transaction whose output 0
+/// pays round(rate × value) to the slashing script and output 1 returns the rest less the slashing
+/// fee to a staker timelock output.
+pub fn slashing_transaction(
+    funding_outpoint: Outpoint,
+    funding: &BabylonOutput,
+    funding_sats: u64,
+    keys: &StakingKeys,
+    params: &SlashingParams,
+) -> Result<Psbt, String> {
+    const RATE_SCALE: u128 = 1_000_000_000_000_000_000;
+    let rate = parse_slashing_rate(&params.slashing_rate)?;
+    let slashed_sats = ((funding_sats as u128 * rate + RATE_SCALE / 2) / RATE_SCALE) as u64;
+    let change_sats = funding_sats.checked_sub(slashed_sats)
+        .and_then(|sats| sats.checked_sub(params.slashing_fee_sats))
+        .filter(|sats| *sats > 0)
+        .ok_or_else(|| format!("Slashing {} sats plus fee {} sats exceeds output value {} sats",
+            slashed_sats, params.slashing_fee_sats, funding_sats))?;
+
+    PsbtBuilder::new()
+        .input(funding_outpoint, funding_sats, funding.script_pubkey())
+        .output(params.slashing_script_pubkey.clone(), slashed_sats)
+        .output(relative_timelock_output(&keys.staker, params.change_lock_time)?, change_sats)
+        .version(Version::ONE)
+        .build()
+}
+
+/// Withdrawal of an expired staking or unbonding output through its timelock leaf
+///
+/// The input's sequence carries the `lock_time` relative lock the leaf checks with CSV.
+pub fn timelock_withdrawal(
+    outpoint: Outpoint,
+    output: &BabylonOutput,
+    sats: u64,
+    lock_time: u16,
+    destination: ScriptBuf,
+    fee_sats: u64,
+) -> Result<Psbt, String> {
+    let withdrawn_sats = sats.checked_sub(fee_sats)
+        .filter(|sats| *sats > 0)
+        .ok_or_else(|| format!("Withdrawal fee {} sats exceeds output value {} sats", fee_sats, sats))?;
+
+    PsbtBuilder::new()
+        .csv_input(outpoint, sats, output.script_pubkey(), lock_time)
+        .output(destination, withdrawn_sats)
+        .build()
+}
+
+/// Witness item lengths of a timelock-leaf spend (for fee estimation)
+pub fn timelock_witness_lens(output: &BabylonOutput) -> Result<Vec<usize>, String> {
+    let control_block = output.control_block(&output.timelock_script)?;
+    Ok(vec![SCHNORR_SIG_LEN, output.timelock_script.len(), control_block.serialize().len()])
+}
+
+/// Witness for the timelock leaf: `<staker sig> <script> <control block>`
+pub fn timelock_witness(output: &BabylonOutput, staker_signature: &[u8]) -> Result<Witness, String> {
+    let mut witness = Witness::new();
+    witness.push(staker_signature);
+    witness.push(output.timelock_script.as_bytes());
+    witness.push(output.control_block(&output.timelock_script)?.serialize());
+    Ok(witness)
+}
+
+/// BIP341 script-path sighash (SIGHASH_DEFAULT) for `leaf`, using the PSBT's witness_utxo prevouts
+pub fn script_spend_sighash(psbt: &Psbt, input_index: usize, leaf: &ScriptBuf) -> Result<[u8; 32], String> {
+    let prevouts: Vec<TxOut> = psbt.inputs.iter()
+        .map(|input| input.witness_utxo.clone().ok_or_else(|| "PSBT input is missing witness_utxo".to_string()))
+        .collect::<Result<_, _>>()?;
+
+    let sighash = SighashCache::new(&psbt.unsigned_tx)
+        .taproot_script_spend_signature_hash(
+            input_index,
+            &Prevouts::All(&prevouts),
+            TapLeafHash::from_script(leaf, LeafVersion::TapScript),
+            TapSighashType::Default,
+        )
+        .map_err(|e| format!("Failed to compute script-path sighash: {:?}", e))?;
+
+    Ok(sighash.to_byte_array())
+}
+
+/// Check a 64-byte BIP340 signature (SIGHASH_DEFAULT) by `pk` over `sighash`
+pub fn verify_signature(sighash: &[u8; 32], pk: &XOnlyPublicKey, signature: &[u8]) -> Result<(), String> {
+    let signature = Signature::from_slice(signature)
+        .map_err(|e| format!("Invalid Schnorr signature ({} bytes): {}", signature.len(), e))?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(*sighash), pk)
+        .map_err(|_| format!("Schnorr signature does not verify for key {}", pk))
+}
+
+/// Witness for the staking output's unbonding leaf
+///
+/// The leaf checks the staker signature first, then the covenant keys in sorted order, so the
+/// stack holds covenant signatures in reverse key order (empty for keys that did not sign)
+/// beneath the staker signature.
+pub fn unbonding_witness(
+    keys: &StakingKeys,
+    staking: &BabylonOutput,
+    staker_signature: &[u8],
+    covenant_signatures: &[(XOnlyPublicKey, Vec<u8>)],
+) -> Result<Witness, String> {
+    let leaf = staking.unbonding_script.as_ref()
+        .ok_or_else(|| "Output has no unbonding leaf".to_string())?;
+
+    let mut sorted = keys.covenant.clone();
+    sorted.sort_by_key(|key| key.serialize());
+    let covenant_stack: Vec<Vec<u8>> = sorted.iter().rev()
+        .map(|key| covenant_signatures.iter()
+            .find(|(pk, _)| pk == key)
+            .map(|(_, signature)| signature.clone())
+            .unwrap_or_default())
+        .collect();
+
+    let signed = covenant_stack.iter().filter(|signature| !signature.is_empty()).count();
+    if signed < keys.covenant_quorum as usize {
+        return Err(format!("{} covenant signatures, quorum is {}", signed, keys.covenant_quorum));
+    }
+
+    let mut witness = Witness::new();
+    for signature in covenant_stack {
+        witness.push(signature);
+    }
+    witness.push(staker_signature);
+    witness.push(leaf.as_bytes());
+    witness.push(staking.control_block(leaf)?.serialize());
+    Ok(witness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ree_types::bitcoin::{consensus::encode, Transaction};
+
+    // Babylon's published btcstaking test data (babylon-test-utils 0.14.0, testdata/btcstaking_params.dat
+    // and btc_delegation_1_{1}.dat / btc_delegation_1_{1,3}.dat), re-encoded as hex
+    const COVENANT_PKS: [&str; 5] = [
+        "247bb11865d68ae89b2c09489ebb75fcae7881ecba80807db111f23799e9d754",
+        "8aa127f03e7ca069c98145848a2060c445c431a15446ddc7769bf616eb172d16",
+        "b319b5c795559e966fcb3befc83c34b9b9011700ea1415e8c8a586624070b6e4",
+        "6034a408587ddfbb49ef765202eaa7536cbd971e6c09e658235460de38048f70",
+        "0eb710db3b8b35ebd29f3585747f0bdb3d0cf234af236daf3aa4dbcd888cb4cb",
+    ];
+    const COVENANT_QUORUM: u32 = 3;
+    const UNBONDING_FEE_SATS: u64 = 1_000;
+    const SLASHING_PK_SCRIPT: &str = "76a914ca38462a2e9ddca920c04c089f85d78eec0de41488ac";
+    const SLASHING_FEE_SATS: u64 = 2_000;
+
+    struct Delegation {
+        staker: &'static str,
+        finality_providers: &'static [&'static str],
+        staking_time: u16,
+        unbonding_time: u16,
+        total_sats: u64,
+        slashing_rate: &'static str,
+        staking_tx: &'static str,
+        slashing_tx: &'static str,
+        delegator_sig: &'static str,
+        unbonding_tx: &'static str,
+        unbonding_slashing_tx: &'static str,
+        delegator_slashing_sig: &'static str,
+        covenant_unbonding_sigs: [(&'static str, &'static str); 3],
+    }
+
+    const DELEGATIONS: [Delegation; 2] = [
+        // btc_delegation_1_{1}.dat: one finality provider
+        Delegation {
+            staker: "3536581446af884de01f854786dd28654104e92956f93e9740f7647ea7e4bac0",
+            finality_providers: &["6356058b600751e9bd623d873815b474d69e44edc15ab9e3e61a7e0c352d1861"],
+            staking_time: 5,
+            unbonding_time: 101,
+            total_sats: 2_000_000_000,
+            slashing_rate: "0.41",
+            staking_tx: "02000000010d1d0f80fe41ab339c40f5cb79d026f0c1c5b9a49608be9925b54096d7c7a45a941d1d4a00ffffffff\
+                02009435770000000022512006269c77475580a596763033a89c3e354f2c8a1d8ddef0158c4add2b4ef656a3\
+                10270000000000001976a914a5e71e24d438356c6c00c8f7bcf8994dc78546f388ac00000000",
+            slashing_tx: "010000000188a76dded866ae1ca2322963709f73b0a5b6df139280021c1ef81ce9959c7c310000000000ffffffff\
+                020035e030000000001976a914ca38462a2e9ddca920c04c089f85d78eec0de41488ac\
+                305755460000000022512051e2353eee501d77021e2173d4b2b1d39a076afb31abcc398c8972bcd87970cd00000000",
+            delegator_sig: "d92db34bbc4e6c961e9d7ee0b15c9f1c623f2e7553caca8ba5c761f620369648\
+                bdfb9f1d31672e63d2a9217c49549599b3ed382a7ca09f04c4222acc0ba1fe98",
+            unbonding_tx: "020000000188a76dded866ae1ca2322963709f73b0a5b6df139280021c1ef81ce9959c7c310000000000ffffffff\
+                0118903577000000002251200553bd4f3d292898599acb97d4933041b33b634ef99285f49f6e8dbdaf7a4e0c00000000",
+            unbonding_slashing_tx: "0100000001830c6915b8a968092c9cfe521ff21eb66dfebae1307ae72fc8a36cdf573f55250000000000ffffffff\
+                026633e030000000001976a914ca38462a2e9ddca920c04c089f85d78eec0de41488ac\
+                e25455460000000022512051e2353eee501d77021e2173d4b2b1d39a076afb31abcc398c8972bcd87970cd00000000",
+            delegator_slashing_sig: "a306b5b0508d0bde1f3a551ebd0844ddac58691c5f2e6b60f95b8bb4dba5831d\
+                38a9b3bea21f79a01a8243807d079461c7a0aa308fb2f7ca17176b03e2ed1193",
+            covenant_unbonding_sigs: [
+                ("247bb11865d68ae89b2c09489ebb75fcae7881ecba80807db111f23799e9d754",
+                 "24a461e2e598cc979bdb658a08199af0d28caf9068287da03cf21bbc3d4fb72e2bf2220c3a6caec1aa51d76a109dfd5fc105565c7c31495dea6acd23d5704dfb"),
+                ("8aa127f03e7ca069c98145848a2060c445c431a15446ddc7769bf616eb172d16",
+                 "dde3d8d12cecd33e04b7b846262fb86bd7d3a983c039903b535360889665bd30b82916341ac9bf5ca4b25c7489cde997a53cdcf9cf0d33ec92a2c0787de32e12"),
+                ("b319b5c795559e966fcb3befc83c34b9b9011700ea1415e8c8a586624070b6e4",
+                 "9d8779e702f7a564088b188c0e1556bcedaebbfedfad48dc32c9d41e6596a49f68afc1ef823d20e96fea9556abc8cf151179340d6d3831d4bd36967f9dde2072"),
+            ],
+        },
+        // btc_delegation_1_{1,3}.dat: two finality providers
+        Delegation {
+            staker: "c46b8bf5b1a4e6422622dd727607454e73f772349a1fd9c391dd7ece3e7b6dc9",
+            finality_providers: &[
+                "6356058b600751e9bd623d873815b474d69e44edc15ab9e3e61a7e0c352d1861",
+                "f1b456415a98d597035e58d23aaf4888c560d529b73412dc5c95ead7e58a8581",
+            ],
+            staking_time: 5,
+            unbonding_time: 101,
+            total_sats: 2_000_000_000,
+            slashing_rate: "0.32",
+            staking_tx: "02000000011868e7e29037a8834037a20ccfc7a92a0e22db2837212af365794d86cf47d900202c8c1500ffffffff\
+                0200943577000000002251209ace0374086fcc38fbf72c3c0ee8e07f6f0100ef645d8947f8309f5ace80e791\
+                10270000000000001976a914c5bb3afabfd580a91ef9d3b1601a3a8f9e22a25788ac00000000",
+            slashing_tx: "01000000014e26997bac83f57e506a0673846f841c642d84d9c9cdb7ea84e0f08872a0d9420000000000ffffffff\
+                0200a02526000000001976a914ca38462a2e9ddca920c04c089f85d78eec0de41488ac\
+                30ec0f51000000002251204ebf4ef53ff7b56d5eff1e26f466e9288f667a8e731eeda24226d68d869810f200000000",
+            delegator_sig: "bebee8ffb3b445cffc8d98d9d856c7cbc1bd4f6b6e6945a7a562dc4314f9657f\
+                7c0bea3281f3b02e84b2a4cc3d9d535f993e22f2b7c15ac638e593c23d1aa72b",
+            unbonding_tx: "02000000014e26997bac83f57e506a0673846f841c642d84d9c9cdb7ea84e0f08872a0d9420000000000ffffffff\
+                0118903577000000002251207facf4de836839c001fb1d28bb11771fd175638c4b0bdfb44fed4426f797355000000000",
+            unbonding_slashing_tx: "0100000001c21f11dfe59b515c3095a0d9cc7459a42e60042ac964e61955e68a50031e72660000000000ffffffff\
+                02c09e2526000000001976a914ca38462a2e9ddca920c04c089f85d78eec0de41488ac\
+                88e90f51000000002251204ebf4ef53ff7b56d5eff1e26f466e9288f667a8e731eeda24226d68d869810f200000000",
+            delegator_slashing_sig: "e1e5ea1c7ec40c34dc1665a370fe285d8940937e7120a4f158ce38cea698dd37\
+                04ea83f9b622ad8243e426c42e1570f2586e199bddc99628ef7456ac680e02f9",
+            covenant_unbonding_sigs: [
+                ("247bb11865d68ae89b2c09489ebb75fcae7881ecba80807db111f23799e9d754",
+                 "241de55ad8c9b1c390fff6e6173bb90fd6cbb3ab336d6ae04cc92a95a3d18e40654703b953fc39c4affd040945f53b4ef5cc232851ab7ba9acd9ecbe1490b01a"),
+                ("8aa127f03e7ca069c98145848a2060c445c431a15446ddc7769bf616eb172d16",
+                 "cf61bdcc1a68813509de8e36f504d8b1cf889ae1fc668db60a571ebdea91b007e8c8c89e42f69d75f2637c483286d9e170e34b795bc7291b5da78ef89e0e7e8e"),
+                ("b319b5c795559e966fcb3befc83c34b9b9011700ea1415e8c8a586624070b6e4",
+                 "759ce9b4f1cf202b4dc94caeddf8fc3e6b116cfca51a5f79fc85ab408f3aad3f986d0d25c4afbb267d2fe42e4186c79598332271d7ca766050b42b50bff3f0a0"),
+            ],
+        },
+    ];
+
+    fn keys(delegation: &Delegation) -> StakingKeys {
+        let finality_providers: Vec<String> = delegation.finality_providers.iter().map(|pk| pk.to_string()).collect();
+        let covenant: Vec<String> = COVENANT_PKS.iter().map(|pk| pk.to_string()).collect();
+        StakingKeys::new(delegation.staker, &finality_providers, &covenant, COVENANT_QUORUM).unwrap()
+    }
+
+    fn tx(tx_hex: &str) -> Transaction {
+        encode::deserialize_hex(tx_hex).unwrap()
+    }
+
+    /// Wrap a published transaction spending `prevout` so the sighash helpers can use it
+    fn psbt_spending(tx_hex: &str, prevout: TxOut) -> Psbt {
+        let mut psbt = Psbt::from_unsigned_tx(tx(tx_hex)).unwrap();
+        psbt.inputs[0].witness_utxo = Some(prevout);
+        psbt
+    }
+
+    #[test]
+    fn staking_output_matches_babylon_output_key() {
+        for delegation in DELEGATIONS.iter() {
+            let staking = staking_output(&keys(delegation), delegation.staking_time).unwrap();
+            assert_eq!(staking.script_pubkey(), tx(delegation.staking_tx).output[0].script_pubkey);
+        }
+    }
+
+    #[test]
+    fn unbonding_transaction_matches_babylon() {
+        for delegation in DELEGATIONS.iter() {
+            let keys = keys(delegation);
+            let staking = staking_output(&keys, delegation.staking_time).unwrap();
+            let unbonding = unbonding_output(&keys, delegation.unbonding_time).unwrap();
+            let staking_tx = tx(delegation.staking_tx);
+
+            let psbt = unbonding_transaction(
+                Outpoint { txid: staking_tx.compute_txid(), vout: 0 },
+                &staking,
+                delegation.total_sats,
+                &unbonding,
+                UNBONDING_FEE_SATS,
+            ).unwrap();
+
+            assert_eq!(encode::serialize_hex(&psbt.unsigned_tx), delegation.unbonding_tx);
+            assert_eq!(unbonding.script_pubkey(), psbt.unsigned_tx.output[0].script_pubkey);
+        }
+    }
+
+    #[test]
+    fn slashing_transactions_match_babylon() {
+        for delegation in DELEGATIONS.iter() {
+            let keys = keys(delegation);
+            let staking = staking_output(&keys, delegation.staking_time).unwrap();
+            let unbonding = unbonding_output(&keys, delegation.unbonding_time).unwrap();
+            let staking_tx = tx(delegation.staking_tx);
+            let unbonding_tx = tx(delegation.unbonding_tx);
+            let params = SlashingParams {
+                slashing_script_pubkey: ScriptBuf::from_bytes(hex::decode(SLASHING_PK_SCRIPT).unwrap()),
+                slashing_rate: delegation.slashing_rate.to_string(),
+                slashing_fee_sats: SLASHING_FEE_SATS,
+                change_lock_time: delegation.unbonding_time,
+            };
+
+            let cases = [
+                (&staking_tx, &staking, delegation.slashing_tx, delegation.delegator_sig),
+                (&unbonding_tx, &unbonding, delegation.unbonding_slashing_tx, delegation.delegator_slashing_sig),
+            ];
+            for (funding_tx, funding, published_tx, published_sig) in cases {
+                let funding_outpoint = Outpoint { txid: funding_tx.compute_txid(), vout: 0 };
+                let funding_sats = funding_tx.output[0].value.to_sat();
+                let psbt = slashing_transaction(funding_outpoint, funding, funding_sats, &keys, &params).unwrap();
+                assert_eq!(encode::serialize_hex(&psbt.unsigned_tx), published_tx);
+
+                // The staker's published signature verifies over the sighash of the built transaction
+                let sighash = script_spend_sighash(&psbt, 0, &funding.slashing_script).unwrap();
+                verify_signature(&sighash, &keys.staker, &hex::decode(published_sig).unwrap()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn unbonding_leaf_verifies_covenant_signatures() {
+        for delegation in DELEGATIONS.iter() {
+            let keys = keys(delegation);
+            let staking = staking_output(&keys, delegation.staking_time).unwrap();
+            let leaf = staking.unbonding_script.clone().unwrap();
+            let psbt = psbt_spending(delegation.unbonding_tx, tx(delegation.staking_tx).output[0].clone());
+            let sighash = script_spend_sighash(&psbt, 0, &leaf).unwrap();
+
+            for (covenant_pk, signature) in delegation.covenant_unbonding_sigs {
+                let signature = hex::decode(signature).unwrap();
+                verify_signature(&sighash, &parse_btc_pk(covenant_pk).unwrap(), &signature).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn slashing_leaves_verify_delegator_signatures() {
+        for delegation in DELEGATIONS.iter() {
+            let keys = keys(delegation);
+            let staking = staking_output(&keys, delegation.staking_time).unwrap();
+            let unbonding = unbonding_output(&keys, delegation.unbonding_time).unwrap();
+
+            // Staking output slashing leaf
+            let psbt = psbt_spending(delegation.slashing_tx, tx(delegation.staking_tx).output[0].clone());
+            let sighash = script_spend_sighash(&psbt, 0, &staking.slashing_script).unwrap();
+            verify_signature(&sighash, &keys.staker, &hex::decode(delegation.delegator_sig).unwrap()).unwrap();
+
+            // Unbonding output slashing leaf
+            let psbt = psbt_spending(delegation.unbonding_slashing_tx, tx(delegation.unbonding_tx).output[0].clone());
+            let sighash = script_spend_sighash(&psbt, 0, &unbonding.slashing_script).unwrap();
+            verify_signature(&sighash, &keys.staker, &hex::decode(delegation.delegator_slashing_sig).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn signatures_fail_under_a_different_leaf() {
+        let delegation = &DELEGATIONS[0];
+        let keys = keys(delegation);
+        let staking = staking_output(&keys, delegation.staking_time).unwrap();
+        let psbt = psbt_spending(delegation.slashing_tx, tx(delegation.staking_tx).output[0].clone());
+        let sighash = script_spend_sighash(&psbt, 0, &staking.timelock_script).unwrap();
+        assert!(verify_signature(&sighash, &keys.staker, &hex::decode(delegation.delegator_sig).unwrap()).is_err());
+    }
+}
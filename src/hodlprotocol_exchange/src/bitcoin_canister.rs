@@ -56,6 +56,13 @@ pub struct GetUtxosResponse {
     pub utxos: Vec<Utxo>,
 }
 
+pub type MillisatoshiPerByte = u64;
+
+#[derive(CandidType, Deserialize)]
+pub struct GetCurrentFeePercentilesRequest {
+    pub network: Network,
+}
+
 pub struct Service(pub Principal);
 impl Service {
     pub async fn bitcoin_get_utxos(&self, arg0: GetUtxosRequest) -> Result<(GetUtxosResponse,)> {
@@ -66,4 +73,16 @@ impl Service {
         };
         ic_cdk::api::call::call_with_payment128(self.0, "bitcoin_get_utxos", (arg0,), cycles).await
     }
+
+    pub async fn bitcoin_get_current_fee_percentiles(
+        &self,
+        arg0: GetCurrentFeePercentilesRequest,
+    ) -> Result<(Vec<MillisatoshiPerByte>,)> {
+        let cycles = match arg0.network {
+            Network::Mainnet => 100_000_000u128,
+            Network::Testnet => 40_000_000u128,
+            Network::Regtest => 0u128,
+        };
+        ic_cdk::api::call::call_with_payment128(self.0, "bitcoin_get_current_fee_percentiles", (arg0,), cycles).await
+    }
 }
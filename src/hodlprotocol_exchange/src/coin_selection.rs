@@ -0,0 +1,432 @@
+// Coin selection over pool / funding UTXOs
+// Branch-and-bound looks for a changeless input set; largest-first with change is the fallback.
+// Rune-bearing UTXOs are only spent when the transaction carries a runestone for that rune.
+use std::cmp::Reverse;
+
+use crate::tx_size::{InputWitness, TxShape};
+use crate::{BitcoinUtxo, FeeBreakdown, FeeQuote};
+
+/// Branch-and-bound gives up after this many search steps (same bound as Bitcoin Core)
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// A UTXO and the runes the indexer reports on it
+#[derive(Clone, Debug)]
+pub struct CoinCandidate {
+    pub utxo: BitcoinUtxo,
+    /// (rune_id, amount) pairs; None when the indexer has not seen the output yet
+    pub runes: Option<Vec<(String, u128)>>,
+}
+
+impl CoinCandidate {
+    fn spendable_under(&self, policy: &RunePolicy) -> bool {
+        match (&self.runes, policy) {
+            (None, _) => false,
+            (Some(runes), RunePolicy::Exclude) => runes.is_empty(),
+            (Some(runes), RunePolicy::Allow(rune_id)) => runes.iter().all(|(id, _)| id == rune_id),
+        }
+    }
+}
+
+/// Which rune-bearing UTXOs a transaction may spend
+#[derive(Clone, Debug)]
+pub enum RunePolicy {
+    /// No runestone: rune-bearing UTXOs are never selected
+    Exclude,
+    /// The runestone has an edict / pointer for this rune: UTXOs holding only it may be selected
+    Allow(String),
+}
+
+/// What the transaction needs from its inputs
+#[derive(Clone, Debug)]
+pub struct SelectionRequest {
+    /// Sum of the non-change outputs
+    pub target_sats: u64,
+    /// Non-change outputs (selected inputs are added as Taproot key-path spends)
+    pub outputs: TxShape,
+    /// scriptPubKey length of the change output
+    pub change_script_len: usize,
+    /// Smallest change output worth creating; less than this goes to the fee
+    pub dust_threshold: u64,
+    pub max_inputs: usize,
+    pub rune_policy: RunePolicy,
+}
+
+/// Inputs chosen for a transaction
+#[derive(Clone, Debug)]
+pub struct Selection {
+    pub inputs: Vec<BitcoinUtxo>,
+    pub input_sats: u64,
+    /// None: no change output (any remainder is paid as fee)
+    pub change_sats: Option<u64>,
+    /// Fee actually paid, including any remainder folded in
+    pub fee: FeeBreakdown,
+}
+
+/// Select inputs covering `target_sats` plus fees, preferring a changeless match
+pub fn select_coins(candidates: &[CoinCandidate], request: &SelectionRequest, fee_quote: &FeeQuote) -> Result<Selection, String> {
+    let eligible = eligible_utxos(candidates, &request.rune_policy);
+    if eligible.is_empty() {
+        return Err(no_eligible_message(candidates, &request.rune_policy));
+    }
+
+    if let Some(selection) = branch_and_bound(&eligible, request, fee_quote) {
+        return Ok(selection);
+    }
+
+    largest_first(&eligible, request, fee_quote)
+}
+
+/// Spend every eligible UTXO (largest first, up to `max_inputs`) with no change output
+///
+/// Used by sweeps and consolidations, where the single output takes whatever is left after
+/// `target_sats` and the fee.
+pub fn select_all(candidates: &[CoinCandidate], request: &SelectionRequest, fee_quote: &FeeQuote) -> Result<Selection, String> {
+    let inputs: Vec<BitcoinUtxo> = eligible_utxos(candidates, &request.rune_policy)
+        .into_iter()
+        .take(request.max_inputs)
+        .cloned()
+        .collect();
+    if inputs.is_empty() {
+        return Err(no_eligible_message(candidates, &request.rune_policy));
+    }
+
+    let input_sats: u64 = inputs.iter().map(|utxo| utxo.value).sum();
+    let fee = fee_for(request, inputs.len(), false, fee_quote);
+    let needed = request.target_sats + fee.fee_sats + request.dust_threshold;
+    if input_sats < needed {
+        return Err(format!(
+            "{} spendable UTXO(s) hold {} sats, need {} sats ({} fee + {} dust)",
+            inputs.len(), input_sats, needed, fee.fee_sats, request.dust_threshold
+        ));
+    }
+
+    Ok(Selection {
+        inputs,
+        input_sats,
+        change_sats: None,
+        fee,
+    })
+}
+
+/// Eligible UTXOs, largest first
+fn eligible_utxos<'a>(candidates: &'a [CoinCandidate], policy: &RunePolicy) -> Vec<&'a BitcoinUtxo> {
+    let mut eligible: Vec<&BitcoinUtxo> = candidates.iter()
+        .filter(|candidate| candidate.spendable_under(policy))
+        .map(|candidate| &candidate.utxo)
+        .collect();
+    eligible.sort_by_key(|utxo| Reverse(utxo.value));
+    eligible
+}
+
+fn no_eligible_message(candidates: &[CoinCandidate], policy: &RunePolicy) -> String {
+    let with_runes = candidates.iter()
+        .filter(|candidate| candidate.runes.as_ref().is_some_and(|runes| !runes.is_empty()))
+        .count();
+    let unindexed = candidates.iter().filter(|candidate| candidate.runes.is_none()).count();
+    format!(
+        "No spendable UTXOs under {:?} ({} found, {} carry runes, {} not yet indexed)",
+        policy, candidates.len(), with_runes, unindexed
+    )
+}
+
+/// Exact fee for `inputs` key-path inputs, with or without the change output
+fn fee_for(request: &SelectionRequest, inputs: usize, with_change: bool, fee_quote: &FeeQuote) -> FeeBreakdown {
+    let mut shape = request.outputs.clone().taproot_inputs(inputs);
+    if with_change {
+        shape = shape.output(request.change_script_len);
+    }
+    fee_quote.for_vsize(shape.vsize())
+}
+
+/// Changeless selection: effective values (value minus the input's own fee) summing to
+/// between the target and the target plus the cost of a change output
+fn branch_and_bound(eligible: &[&BitcoinUtxo], request: &SelectionRequest, fee_quote: &FeeQuote) -> Option<Selection> {
+    let input_fee = (fee_quote.sat_per_vb * InputWitness::TaprootKeyPath.weight()).div_ceil(4);
+    let change_fee = fee_for(request, 0, true, fee_quote).fee_sats - fee_for(request, 0, false, fee_quote).fee_sats;
+
+    let pool: Vec<(&BitcoinUtxo, u64)> = eligible.iter()
+        .filter(|utxo| utxo.value > input_fee)
+        .map(|utxo| (*utxo, utxo.value - input_fee))
+        .collect();
+
+    let target = request.target_sats + fee_for(request, 0, false, fee_quote).fee_sats;
+    let upper = target + change_fee + input_fee;
+
+    let mut search = BnbSearch {
+        values: pool.iter().map(|(_, effective)| *effective).collect(),
+        target,
+        upper,
+        max_inputs: request.max_inputs,
+        tries: 0,
+        selected: vec![],
+        best: None,
+    };
+    let remaining = search.values.iter().sum();
+    search.explore(0, 0, remaining);
+
+    let (_, indexes) = search.best?;
+    let inputs: Vec<BitcoinUtxo> = indexes.iter().map(|i| pool[*i].0.clone()).collect();
+    let input_sats: u64 = inputs.iter().map(|utxo| utxo.value).sum();
+
+    // Re-check with the exact transaction size (varints, marker / flag)
+    let fee = fee_for(request, inputs.len(), false, fee_quote);
+    let paid = input_sats.checked_sub(request.target_sats)?;
+    if paid < fee.fee_sats {
+        return None;
+    }
+
+    Some(Selection {
+        inputs,
+        input_sats,
+        change_sats: None,
+        fee: FeeBreakdown { fee_sats: paid, ..fee },
+    })
+}
+
+struct BnbSearch {
+    values: Vec<u64>,  // Effective values, largest first
+    target: u64,
+    upper: u64,
+    max_inputs: usize,
+    tries: usize,
+    selected: Vec<usize>,
+    best: Option<(u64, Vec<usize>)>,  // (excess over target, indexes)
+}
+
+impl BnbSearch {
+    /// Depth-first over include / exclude of `values[index..]`; `remaining` is their sum
+    fn explore(&mut self, index: usize, sum: u64, remaining: u64) {
+        self.tries += 1;
+        if self.tries > BNB_MAX_TRIES || sum > self.upper || sum + remaining < self.target {
+            return;
+        }
+
+        if sum >= self.target {
+            let excess = sum - self.target;
+            let better = match &self.best {
+                None => true,
+                Some((best_excess, best)) => {
+                    excess < *best_excess || (excess == *best_excess && self.selected.len() < best.len())
+                }
+            };
+            if better {
+                self.best = Some((excess, self.selected.clone()));
+            }
+            return;
+        }
+
+        if index >= self.values.len() || self.selected.len() >= self.max_inputs {
+            return;
+        }
+
+        let value = self.values[index];
+
+        self.selected.push(index);
+        self.explore(index + 1, sum + value, remaining - value);
+        self.selected.pop();
+
+        self.explore(index + 1, sum, remaining - value);
+    }
+}
+
+/// Add the largest UTXOs until the outputs, fee and (if worthwhile) change are covered
+fn largest_first(eligible: &[&BitcoinUtxo], request: &SelectionRequest, fee_quote: &FeeQuote) -> Result<Selection, String> {
+    let mut inputs = vec![];
+    let mut input_sats = 0u64;
+
+    for utxo in eligible.iter().take(request.max_inputs) {
+        inputs.push((*utxo).clone());
+        input_sats += utxo.value;
+
+        let with_change = fee_for(request, inputs.len(), true, fee_quote);
+        if let Some(change) = input_sats.checked_sub(request.target_sats + with_change.fee_sats) {
+            if change >= request.dust_threshold {
+                return Ok(Selection {
+                    inputs,
+                    input_sats,
+                    change_sats: Some(change),
+                    fee: with_change,
+                });
+            }
+        }
+
+        // Change would be dust: pay the remainder as fee
+        let without_change = fee_for(request, inputs.len(), false, fee_quote);
+        if input_sats >= request.target_sats + without_change.fee_sats {
+            let paid = input_sats - request.target_sats;
+            return Ok(Selection {
+                inputs,
+                input_sats,
+                change_sats: None,
+                fee: FeeBreakdown { fee_sats: paid, ..without_change },
+            });
+        }
+    }
+
+    Err(format!(
+        "Insufficient funds: {} spendable UTXO(s) ({} sats within {} inputs) cannot cover {} sats plus fees",
+        eligible.len(), input_sats, request.max_inputs, request.target_sats
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_size::P2TR_SCRIPT_LEN;
+    use crate::{FeeUrgency, UtxoStatus};
+
+    const BLST: &str = "840000:1";
+    const DUST: u64 = 1_000;
+
+    fn utxo(value: u64, vout: u32) -> BitcoinUtxo {
+        BitcoinUtxo {
+            txid: "11".repeat(32),
+            vout,
+            value,
+            status: UtxoStatus { confirmed: true, block_height: Some(100), block_hash: None },
+        }
+    }
+
+    /// Plain BTC UTXOs, one per value
+    fn candidates(values: &[u64]) -> Vec<CoinCandidate> {
+        values.iter().enumerate()
+            .map(|(vout, value)| CoinCandidate { utxo: utxo(*value, vout as u32), runes: Some(vec![]) })
+            .collect()
+    }
+
+    fn quote(sat_per_vb: u64) -> FeeQuote {
+        FeeQuote { urgency: FeeUrgency::Consolidation, percentile: 50, market_sat_per_vb: sat_per_vb, sat_per_vb }
+    }
+
+    fn request(target_sats: u64) -> SelectionRequest {
+        SelectionRequest {
+            target_sats,
+            outputs: TxShape::new().p2tr_output(),
+            change_script_len: P2TR_SCRIPT_LEN,
+            dust_threshold: DUST,
+            max_inputs: 100,
+            rune_policy: RunePolicy::Exclude,
+        }
+    }
+
+    /// Inputs pay exactly the outputs, the fee and the change
+    fn assert_balanced(selection: &Selection, request: &SelectionRequest) {
+        assert_eq!(
+            selection.input_sats,
+            request.target_sats + selection.fee.fee_sats + selection.change_sats.unwrap_or_default()
+        );
+        assert_eq!(selection.input_sats, selection.inputs.iter().map(|utxo| utxo.value).sum::<u64>());
+    }
+
+    #[test]
+    fn insufficient_funds() {
+        let request = request(50_000);
+        let error = select_coins(&candidates(&[20_000, 20_000, 10_000]), &request, &quote(2)).unwrap_err();
+        assert!(error.starts_with("Insufficient funds"), "{}", error);
+    }
+
+    #[test]
+    fn max_inputs_caps_the_selection() {
+        let mut request = request(50_000);
+        request.max_inputs = 2;
+        let error = select_coins(&candidates(&[20_000, 20_000, 20_000]), &request, &quote(1)).unwrap_err();
+        assert!(error.starts_with("Insufficient funds"), "{}", error);
+    }
+
+    #[test]
+    fn change_above_dust_gets_an_output() {
+        let request = request(50_000);
+        let quote = quote(2);
+        let selection = select_coins(&candidates(&[80_000]), &request, &quote).unwrap();
+
+        let fee = fee_for(&request, 1, true, &quote);
+        assert_eq!(selection.change_sats, Some(80_000 - 50_000 - fee.fee_sats));
+        assert_eq!(selection.fee.fee_sats, fee.fee_sats);
+        assert_balanced(&selection, &request);
+    }
+
+    #[test]
+    fn dust_change_goes_to_the_fee() {
+        let request = request(50_000);
+        let quote = quote(1);
+        // One sat short of a change output worth creating
+        let value = 50_000 + fee_for(&request, 1, true, &quote).fee_sats + DUST - 1;
+        let selection = select_coins(&candidates(&[value]), &request, &quote).unwrap();
+
+        assert_eq!(selection.change_sats, None);
+        assert_eq!(selection.fee.fee_sats, value - 50_000);
+        assert!(selection.fee.fee_sats > fee_for(&request, 1, false, &quote).fee_sats);
+        assert_balanced(&selection, &request);
+    }
+
+    #[test]
+    fn change_at_the_dust_threshold_is_kept() {
+        let request = request(50_000);
+        let quote = quote(1);
+        let value = 50_000 + fee_for(&request, 1, true, &quote).fee_sats + DUST;
+        let selection = select_coins(&candidates(&[value]), &request, &quote).unwrap();
+
+        assert_eq!(selection.change_sats, Some(DUST));
+        assert_balanced(&selection, &request);
+    }
+
+    #[test]
+    fn branch_and_bound_finds_a_changeless_match() {
+        let request = request(50_000);
+        let quote = quote(1);
+        let input_fee = InputWitness::TaprootKeyPath.weight().div_ceil(4);
+        let base_fee = fee_for(&request, 0, false, &quote).fee_sats;
+
+        // The two small UTXOs cover the target and fee with a few sats to spare; the large one
+        // alone would need change
+        let selection = select_coins(
+            &candidates(&[100_000, 25_000 + input_fee + base_fee + 10, 25_000 + input_fee]),
+            &request,
+            &quote,
+        ).unwrap();
+
+        assert_eq!(selection.change_sats, None);
+        assert_eq!(selection.inputs.iter().map(|utxo| utxo.vout).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(selection.fee.fee_sats >= fee_for(&request, 2, false, &quote).fee_sats);
+        assert_balanced(&selection, &request);
+    }
+
+    #[test]
+    fn rune_policy_filters_candidates() {
+        let coins = vec![
+            CoinCandidate { utxo: utxo(50_000, 0), runes: None },                                       // Not indexed
+            CoinCandidate { utxo: utxo(60_000, 1), runes: Some(vec![(BLST.to_string(), 10)]) },         // BLST only
+            CoinCandidate { utxo: utxo(70_000, 2), runes: Some(vec![("840000:2".to_string(), 10)]) },   // Other rune
+        ];
+
+        let error = select_coins(&coins, &request(10_000), &quote(1)).unwrap_err();
+        assert!(error.starts_with("No spendable UTXOs"), "{}", error);
+
+        let mut allow = request(10_000);
+        allow.rune_policy = RunePolicy::Allow(BLST.to_string());
+        let selection = select_coins(&coins, &allow, &quote(1)).unwrap();
+        assert_eq!(selection.inputs.iter().map(|utxo| utxo.vout).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn select_all_spends_every_eligible_utxo_without_change() {
+        let mut request = request(0);
+        request.max_inputs = 3;
+        let selection = select_all(&candidates(&[5_000, 40_000, 20_000, 1_000]), &request, &quote(1)).unwrap();
+
+        // Largest first, capped at max_inputs
+        assert_eq!(selection.inputs.iter().map(|utxo| utxo.value).collect::<Vec<_>>(), vec![40_000, 20_000, 5_000]);
+        assert_eq!(selection.change_sats, None);
+        assert_eq!(selection.fee.fee_sats, fee_for(&request, 3, false, &quote(1)).fee_sats);
+    }
+
+    #[test]
+    fn select_all_needs_more_than_dust_left() {
+        let request = request(0);
+        let quote = quote(1);
+        let fee = fee_for(&request, 1, false, &quote).fee_sats;
+        let error = select_all(&candidates(&[fee + DUST - 1]), &request, &quote).unwrap_err();
+        assert!(error.contains("need"), "{}", error);
+        select_all(&candidates(&[fee + DUST]), &request, &quote).unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+// Finality provider diversification
+// Turns a pool's allocation policy (explicit weights, or a scoring rule over the registry) into
+// target shares, and splits new stake across providers so the pool drifts towards its targets.
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use crate::{FinalityProvider, FpAllocationPolicy};
+
+/// Shares are in basis points of the pool's staked BTC
+pub const TOTAL_BPS: u32 = 10_000;
+
+/// Commission in percent (unparseable counts as 100%)
+pub fn commission_pct(fp: &FinalityProvider) -> f64 {
+    fp.commission.parse::<f64>().unwrap_or(100.0)
+}
+
+/// Target share (bps, summing to TOTAL_BPS) per finality provider key
+///
+/// - explicit targets: their weights, skipping providers that are jailed, slashed or inactive
+/// - scoring rule (`auto_count`): the N eligible providers with the lowest commission (ties go
+///   to higher voting power), under `max_commission_pct`, equally weighted
+/// - neither: the pool's own finality provider
+///
+/// `max_share_bps` then caps every share, spreading the excess over the others.
+pub fn target_shares(
+    policy: &FpAllocationPolicy,
+    pool_fp: &str,
+    fps: &[FinalityProvider],
+) -> Result<Vec<(String, u32)>, String> {
+    let eligible = |btc_pk: &str| fps.iter().any(|fp| fp.btc_pk_hex == btc_pk && fp.is_eligible());
+
+    let weights: Vec<(String, u64)> = if !policy.targets.is_empty() {
+        policy.targets.iter()
+            .filter(|target| target.weight > 0 && eligible(&target.fp_btc_pk))
+            .map(|target| (target.fp_btc_pk.clone(), target.weight as u64))
+            .collect()
+    } else if policy.auto_count > 0 {
+        let mut ranked: Vec<&FinalityProvider> = fps.iter()
+            .filter(|fp| fp.is_eligible())
+            .filter(|fp| policy.max_commission_pct.is_none_or(|max| commission_pct(fp) <= max))
+            .collect();
+        ranked.sort_by(|a, b| {
+            commission_pct(a).total_cmp(&commission_pct(b)).then(b.voting_power.cmp(&a.voting_power))
+        });
+        ranked.into_iter()
+            .take(policy.auto_count as usize)
+            .map(|fp| (fp.btc_pk_hex.clone(), 1))
+            .collect()
+    } else if eligible(pool_fp) {
+        vec![(pool_fp.to_string(), 1)]
+    } else {
+        vec![]
+    };
+
+    if weights.is_empty() {
+        return Err("Allocation policy has no eligible finality providers".to_string());
+    }
+
+    let total_weight: u64 = weights.iter().map(|(_, weight)| weight).sum();
+    let mut shares: Vec<(String, u32)> = weights.into_iter()
+        .map(|(btc_pk, weight)| (btc_pk, (weight * TOTAL_BPS as u64 / total_weight) as u32))
+        .collect();
+    let rounding = TOTAL_BPS - shares.iter().map(|(_, share)| share).sum::<u32>();
+    shares[0].1 += rounding;
+
+    cap_shares(&mut shares, policy.max_share_bps)?;
+    Ok(shares)
+}
+
+/// Concentration limit: no share above `max_share_bps`, excess spread pro rata over the rest
+fn cap_shares(shares: &mut [(String, u32)], max_share_bps: u32) -> Result<(), String> {
+    if max_share_bps == 0 || max_share_bps >= TOTAL_BPS {
+        return Ok(());
+    }
+    if max_share_bps as u64 * (shares.len() as u64) < TOTAL_BPS as u64 {
+        return Err(format!(
+            "Concentration limit of {} bps needs at least {} eligible finality providers, policy has {}",
+            max_share_bps,
+            TOTAL_BPS.div_ceil(max_share_bps),
+            shares.len()
+        ));
+    }
+
+    loop {
+        let excess: u32 = shares.iter().map(|(_, share)| share.saturating_sub(max_share_bps)).sum();
+        if excess == 0 {
+            return Ok(());
+        }
+        for (_, share) in shares.iter_mut() {
+            *share = (*share).min(max_share_bps);
+        }
+
+        let open_weight: u64 = shares.iter()
+            .filter(|(_, share)| *share < max_share_bps)
+            .map(|(_, share)| *share as u64)
+            .sum();
+        let mut given = 0;
+        for (_, share) in shares.iter_mut().filter(|(_, share)| *share < max_share_bps) {
+            let add = (excess as u64 * *share as u64).checked_div(open_weight).unwrap_or(0) as u32;
+            *share += add;
+            given += add;
+        }
+
+        // Rounding leftovers go wherever there is room
+        let mut left = excess - given;
+        for (_, share) in shares.iter_mut() {
+            let add = max_share_bps.saturating_sub(*share).min(left);
+            *share += add;
+            left -= add;
+        }
+    }
+}
+
+/// Split `amount` sats of new stake across the targets
+///
+/// Each provider's deficit is its target share of (current + amount) less what it already holds;
+/// the amount is split pro rata over the deficits (over the target shares when every provider is
+/// at or above target). The smallest splits are dropped until every split reaches
+/// `min_split_sats` and there are at most `max_splits`. Empty when even a single split is too small.
+pub fn plan_splits(
+    shares: &[(String, u32)],
+    current: &BTreeMap<String, u64>,
+    amount: u64,
+    min_split_sats: u64,
+    max_splits: usize,
+) -> Vec<(String, u64)> {
+    let total = current.values().sum::<u64>() + amount;
+    let mut weights: Vec<(String, u64)> = shares.iter()
+        .map(|(btc_pk, share)| {
+            let target = (total as u128 * *share as u128 / TOTAL_BPS as u128) as u64;
+            (btc_pk.clone(), target.saturating_sub(current.get(btc_pk).copied().unwrap_or(0)))
+        })
+        .filter(|(_, deficit)| *deficit > 0)
+        .collect();
+    if weights.is_empty() {
+        weights = shares.iter().map(|(btc_pk, share)| (btc_pk.clone(), *share as u64)).collect();
+    }
+    weights.sort_by_key(|(_, weight)| Reverse(*weight));
+    weights.truncate(max_splits.max(1));
+
+    while !weights.is_empty() {
+        let weight_total: u128 = weights.iter().map(|(_, weight)| *weight as u128).sum();
+        let mut splits: Vec<(String, u64)> = weights.iter()
+            .map(|(btc_pk, weight)| (btc_pk.clone(), (amount as u128 * *weight as u128 / weight_total) as u64))
+            .collect();
+        let rounding = amount - splits.iter().map(|(_, sats)| sats).sum::<u64>();
+        splits[0].1 += rounding;
+
+        if splits.iter().all(|(_, sats)| *sats >= min_split_sats && *sats > 0) {
+            return splits;
+        }
+        weights.pop();
+    }
+    vec![]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FPDescription, FpAllocationTarget};
+
+    fn fp(btc_pk: &str, commission: &str, voting_power: u64) -> FinalityProvider {
+        FinalityProvider {
+            btc_pk_hex: btc_pk.to_string(),
+            babylon_address: String::new(),
+            description: FPDescription {
+                moniker: btc_pk.to_string(),
+                identity: String::new(),
+                website: String::new(),
+                details: String::new(),
+            },
+            commission: commission.to_string(),
+            jailed: false,
+            slashed: false,
+            voting_power,
+            estimated_apy: 0.0,
+        }
+    }
+
+    fn registry() -> Vec<FinalityProvider> {
+        vec![fp("a", "0.05", 100), fp("b", "0.05", 300), fp("c", "0.10", 500), fp("d", "0.02", 10), fp("e", "0.20", 50)]
+    }
+
+    fn weighted(targets: &[(&str, u32)]) -> FpAllocationPolicy {
+        FpAllocationPolicy {
+            targets: targets.iter()
+                .map(|(btc_pk, weight)| FpAllocationTarget { fp_btc_pk: btc_pk.to_string(), weight: *weight })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn total(shares: &[(String, u32)]) -> u32 {
+        shares.iter().map(|(_, share)| share).sum()
+    }
+
+    fn bps(shares: &[(String, u32)]) -> Vec<u32> {
+        shares.iter().map(|(_, share)| *share).collect()
+    }
+
+    #[test]
+    fn weight_rounding_goes_to_the_first_target() {
+        let shares = target_shares(&weighted(&[("a", 1), ("b", 1), ("c", 1)]), "a", &registry()).unwrap();
+        assert_eq!(bps(&shares), vec![3_334, 3_333, 3_333]);
+
+        let shares = target_shares(&weighted(&[("a", 2), ("b", 3), ("c", 2)]), "a", &registry()).unwrap();
+        assert_eq!(bps(&shares), vec![2_858, 4_285, 2_857]);
+        assert_eq!(total(&shares), TOTAL_BPS);
+    }
+
+    #[test]
+    fn ineligible_and_zero_weight_targets_are_skipped() {
+        let mut fps = registry();
+        fps[0].jailed = true;
+        fps[1].slashed = true;
+        fps[2].voting_power = 0;
+
+        let shares = target_shares(&weighted(&[("a", 1), ("b", 1), ("c", 1), ("d", 0), ("e", 3)]), "a", &fps).unwrap();
+        assert_eq!(shares, vec![("e".to_string(), TOTAL_BPS)]);
+
+        let error = target_shares(&weighted(&[("a", 1), ("d", 0)]), "a", &fps).unwrap_err();
+        assert!(error.contains("no eligible"), "{}", error);
+    }
+
+    #[test]
+    fn scoring_rule_ranks_by_commission_then_voting_power() {
+        let policy = FpAllocationPolicy { auto_count: 3, ..Default::default() };
+        let shares = target_shares(&policy, "a", &registry()).unwrap();
+        assert_eq!(shares, vec![("d".to_string(), 3_334), ("b".to_string(), 3_333), ("a".to_string(), 3_333)]);
+
+        let capped = FpAllocationPolicy { auto_count: 5, max_commission_pct: Some(0.05), ..Default::default() };
+        let shares = target_shares(&capped, "a", &registry()).unwrap();
+        assert_eq!(shares.iter().map(|(btc_pk, _)| btc_pk.as_str()).collect::<Vec<_>>(), vec!["d", "b", "a"]);
+    }
+
+    #[test]
+    fn default_policy_uses_the_pool_fp() {
+        let shares = target_shares(&FpAllocationPolicy::default(), "c", &registry()).unwrap();
+        assert_eq!(shares, vec![("c".to_string(), TOTAL_BPS)]);
+        assert!(target_shares(&FpAllocationPolicy::default(), "unknown", &registry()).is_err());
+    }
+
+    #[test]
+    fn concentration_cap_spreads_the_excess_pro_rata() {
+        let mut policy = weighted(&[("a", 8), ("b", 1), ("c", 1)]);
+        policy.max_share_bps = 5_000;
+        let shares = target_shares(&policy, "a", &registry()).unwrap();
+        assert_eq!(bps(&shares), vec![5_000, 2_500, 2_500]);
+    }
+
+    #[test]
+    fn concentration_cap_can_take_several_rounds() {
+        // The first spread pushes b over the cap too
+        let mut policy = weighted(&[("a", 6), ("b", 2), ("c", 1)]);
+        policy.max_share_bps = 4_000;
+        let shares = target_shares(&policy, "a", &registry()).unwrap();
+        assert_eq!(bps(&shares), vec![4_000, 4_000, 2_000]);
+    }
+
+    #[test]
+    fn concentration_cap_keeps_shares_summing_to_total() {
+        for (weights, max_share_bps) in [
+            (vec![("a", 7), ("b", 3), ("c", 3), ("d", 1)], 3_000),
+            (vec![("a", 100), ("b", 1), ("c", 1)], 3_334),
+            (vec![("a", 5), ("b", 5), ("c", 5), ("d", 5), ("e", 1)], 2_100),
+        ] {
+            let mut policy = weighted(&weights);
+            policy.max_share_bps = max_share_bps;
+            let shares = target_shares(&policy, "a", &registry()).unwrap();
+            assert_eq!(total(&shares), TOTAL_BPS, "{:?}", shares);
+            assert!(shares.iter().all(|(_, share)| *share <= max_share_bps), "{:?}", shares);
+        }
+    }
+
+    #[test]
+    fn concentration_cap_needs_enough_providers() {
+        let mut policy = weighted(&[("a", 1), ("b", 1)]);
+        policy.max_share_bps = 4_000;
+        let error = target_shares(&policy, "a", &registry()).unwrap_err();
+        assert!(error.contains("at least 3"), "{}", error);
+
+        // Caps of 0 or >= 100% are no-ops
+        for max_share_bps in [0, TOTAL_BPS] {
+            policy.max_share_bps = max_share_bps;
+            assert_eq!(bps(&target_shares(&policy, "a", &registry()).unwrap()), vec![5_000, 5_000]);
+        }
+    }
+
+    fn shares(targets: &[(&str, u32)]) -> Vec<(String, u32)> {
+        targets.iter().map(|(btc_pk, share)| (btc_pk.to_string(), *share)).collect()
+    }
+
+    fn current(holdings: &[(&str, u64)]) -> BTreeMap<String, u64> {
+        holdings.iter().map(|(btc_pk, sats)| (btc_pk.to_string(), *sats)).collect()
+    }
+
+    #[test]
+    fn splits_fill_deficits() {
+        // Target 50/50 of 200k: a holds 100k already, so b gets all new stake
+        let splits = plan_splits(&shares(&[("a", 5_000), ("b", 5_000)]), &current(&[("a", 100_000)]), 100_000, 0, 10);
+        assert_eq!(splits, vec![("b".to_string(), 100_000)]);
+
+        // Deficits 30k / 10k → split 3:1, rounding to the largest
+        let splits = plan_splits(&shares(&[("a", 5_000), ("b", 5_000)]), &current(&[("a", 20_000), ("b", 40_000)]), 40_001, 0, 10);
+        assert_eq!(splits, vec![("a".to_string(), 30_001), ("b".to_string(), 10_000)]);
+    }
+
+    #[test]
+    fn splits_follow_targets_when_nothing_is_below_target() {
+        // Holdings already match 25/75 after rounding: weight by share instead
+        let targets = shares(&[("a", 2_500), ("b", 7_500)]);
+        let splits = plan_splits(&targets, &current(&[("a", 1_000), ("b", 3_000)]), 1, 0, 10);
+        assert_eq!(splits, vec![("b".to_string(), 1)]);
+
+        assert!(plan_splits(&targets, &current(&[("a", 1_000), ("b", 3_000)]), 0, 0, 10).is_empty());
+    }
+
+    #[test]
+    fn small_splits_are_dropped() {
+        let targets = shares(&[("a", 6_000), ("b", 3_000), ("c", 1_000)]);
+
+        // c's 10k split is below the minimum: a and b take it
+        let splits = plan_splits(&targets, &BTreeMap::new(), 100_000, 20_000, 10);
+        assert_eq!(splits, vec![("a".to_string(), 66_667), ("b".to_string(), 33_333)]);
+
+        // At most one split
+        let splits = plan_splits(&targets, &BTreeMap::new(), 100_000, 0, 1);
+        assert_eq!(splits, vec![("a".to_string(), 100_000)]);
+
+        // Even a single split is too small
+        assert!(plan_splits(&targets, &BTreeMap::new(), 10_000, 20_000, 10).is_empty());
+    }
+}
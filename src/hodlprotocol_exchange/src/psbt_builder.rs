@@ -0,0 +1,317 @@
+// PSBT construction on ree_types' bitcoin (0.32)
+// Every transaction the canister signs is assembled here, from typed outpoints, so inputs always
+// carry witness_utxo and txid byte order is decided once - when the outpoint is created.
+use std::fmt;
+use std::str::FromStr;
+
+use ree_types::bitcoin::{
+    absolute::LockTime, hashes::Hash, psbt::Psbt, transaction::Version, Address, Amount, OutPoint,
+    ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use ree_types::{CoinBalances, Utxo};
+
+use crate::btc_network;
+
+/// Reference to a transaction output
+///
+/// Txids are hashed and serialized in internal byte order, but explorers, mempool.space, the runes
+/// indexer and REE print them reversed ("display" order). The constructors name the order they
+/// expect; `Display` always prints `txid:vout` in display order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Outpoint {
+    pub txid: Txid,
+    pub vout: u32,
+}
+
+impl Outpoint {
+    /// Txid bytes in internal order (Bitcoin canister `get_utxos` outpoints)
+    pub fn from_internal_bytes(txid: &[u8], vout: u32) -> Result<Self, String> {
+        let txid = Txid::from_slice(txid)
+            .map_err(|e| format!("Invalid txid bytes ({} bytes): {}", txid.len(), e))?;
+        Ok(Self { txid, vout })
+    }
+
+    /// Hex txid in display order (explorers, mempool.space, runes indexer, REE)
+    pub fn from_display_hex(txid: &str, vout: u32) -> Result<Self, String> {
+        let txid = Txid::from_str(txid).map_err(|e| format!("Invalid txid {}: {}", txid, e))?;
+        Ok(Self { txid, vout })
+    }
+
+    /// `txid:vout` with a display-order txid
+    pub fn parse(outpoint: &str) -> Result<Self, String> {
+        let (txid, vout) = outpoint.rsplit_once(':')
+            .ok_or_else(|| format!("Invalid outpoint {} (expected txid:vout)", outpoint))?;
+        let vout = vout.parse::<u32>()
+            .map_err(|e| format!("Invalid vout in {}: {}", outpoint, e))?;
+        Self::from_display_hex(txid, vout)
+    }
+
+    /// Hex txid in internal order (raw transaction bytes, Bitcoin canister)
+    pub fn internal_txid_hex(&self) -> String {
+        hex::encode(self.txid.to_byte_array())
+    }
+
+    /// REE UTXO for `ree_pool_sign` / state tracking
+    pub fn to_utxo(self, sats: u64, coins: CoinBalances) -> Result<Utxo, String> {
+        Utxo::try_from(self.to_string(), coins, sats)
+            .map_err(|e| format!("Invalid UTXO {}: {:?}", self, e))
+    }
+}
+
+impl From<Outpoint> for OutPoint {
+    fn from(outpoint: Outpoint) -> Self {
+        OutPoint { txid: outpoint.txid, vout: outpoint.vout }
+    }
+}
+
+impl fmt::Display for Outpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+/// Address checked against the network the canister is deployed on
+pub fn parse_address(address: &str) -> Result<Address, String> {
+    Address::from_str(address)
+        .map_err(|e| format!("Invalid address {}: {}", address, e))?
+        .require_network(btc_network().ree_network())
+        .map_err(|e| format!("Address network mismatch for {}: {}", address, e))
+}
+
+/// Unsigned transaction (version 2 unless set), wrapped as a PSBT with witness_utxo on every input
+#[derive(Clone, Debug, Default)]
+pub struct PsbtBuilder {
+    inputs: Vec<(Outpoint, TxOut, Option<u16>)>,  // Optional BIP68 relative lock in blocks
+    outputs: Vec<TxOut>,
+    rbf: bool,
+    version: Option<Version>,
+}
+
+impl PsbtBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spend `outpoint`, a `sats` output locked to `script_pubkey`
+    pub fn input(mut self, outpoint: Outpoint, sats: u64, script_pubkey: ScriptBuf) -> Self {
+        self.inputs.push((outpoint, TxOut { value: Amount::from_sat(sats), script_pubkey }, None));
+        self
+    }
+
+    /// Spend `outpoint` through a CHECKSEQUENCEVERIFY script path: the input's sequence carries a
+    /// relative lock of `blocks` (BIP68), which must be at least the script's lock
+    pub fn csv_input(mut self, outpoint: Outpoint, sats: u64, script_pubkey: ScriptBuf, blocks: u16) -> Self {
+        self.inputs.push((outpoint, TxOut { value: Amount::from_sat(sats), script_pubkey }, Some(blocks)));
+        self
+    }
+
+    pub fn output(mut self, script_pubkey: ScriptBuf, sats: u64) -> Self {
+        self.outputs.push(TxOut { value: Amount::from_sat(sats), script_pubkey });
+        self
+    }
+
+    /// Zero-value output carrying an OP_RETURN script (e.g. an enciphered runestone)
+    pub fn op_return(self, script: Vec<u8>) -> Self {
+        self.output(ScriptBuf::from_bytes(script), 0)
+    }
+
+    /// Signal BIP125 replaceability on every input (canister-broadcast transactions only;
+    /// REE-submitted transactions keep final sequences)
+    pub fn rbf(mut self) -> Self {
+        self.rbf = true;
+        self
+    }
+
+    /// Transaction version other than 2 (BIP68 relative locks need version 2)
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    pub fn build(self) -> Result<Psbt, String> {
+        if self.inputs.is_empty() {
+            return Err("Transaction has no inputs".to_string());
+        }
+        if self.outputs.is_empty() {
+            return Err("Transaction has no outputs".to_string());
+        }
+
+        let sequence = if self.rbf { Sequence::ENABLE_RBF_NO_LOCKTIME } else { Sequence::MAX };
+        let unsigned_tx = Transaction {
+            version: self.version.unwrap_or(Version::TWO),
+            lock_time: LockTime::ZERO,
+            input: self.inputs.iter().map(|(outpoint, _, relative_lock)| TxIn {
+                previous_output: (*outpoint).into(),
+                script_sig: ScriptBuf::new(),
+                // A relative lock also signals replaceability (any sequence below 0xfffffffe)
+                sequence: relative_lock.map(Sequence::from_height).unwrap_or(sequence),
+                witness: Witness::new(),
+            }).collect(),
+            output: self.outputs,
+        };
+
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx)
+            .map_err(|e| format!("Failed to create PSBT: {:?}", e))?;
+        for (input, (_, prevout, _)) in psbt.inputs.iter_mut().zip(self.inputs) {
+            input.witness_utxo = Some(prevout);
+        }
+
+        Ok(psbt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ree_types::bitcoin::{
+        consensus::encode,
+        key::{Keypair, TapTweak},
+        secp256k1::{Message, Secp256k1, SecretKey},
+        sighash::{Prevouts, SighashCache, TapSighashType},
+    };
+
+    const TXID_DISPLAY: &str = "0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20";
+
+    fn p2tr(byte: u8) -> ScriptBuf {
+        ScriptBuf::from_bytes([vec![0x51, 0x20], vec![byte; 32]].concat())
+    }
+
+    fn outpoint(byte: u8, vout: u32) -> Outpoint {
+        Outpoint::from_display_hex(&format!("{:02x}", byte).repeat(32), vout).unwrap()
+    }
+
+    fn three_input_psbt() -> Psbt {
+        PsbtBuilder::new()
+            .input(outpoint(0xaa, 0), 50_000, p2tr(1))
+            .input(outpoint(0xbb, 3), 20_000, p2tr(2))
+            .input(outpoint(0xcc, 1), 7_000, p2tr(3))
+            .output(p2tr(4), 60_000)
+            .output(p2tr(1), 16_000)
+            .build()
+            .unwrap()
+    }
+
+    fn key_path_sighash(psbt: &Psbt, input_index: usize) -> [u8; 32] {
+        let prevouts: Vec<TxOut> = psbt.inputs.iter().map(|input| input.witness_utxo.clone().unwrap()).collect();
+        SighashCache::new(&psbt.unsigned_tx)
+            .taproot_key_spend_signature_hash(input_index, &Prevouts::All(&prevouts), TapSighashType::Default)
+            .unwrap()
+            .to_byte_array()
+    }
+
+    #[test]
+    fn outpoint_byte_order() {
+        let outpoint = Outpoint::from_display_hex(TXID_DISPLAY, 7).unwrap();
+        let mut internal = hex::decode(TXID_DISPLAY).unwrap();
+        internal.reverse();
+
+        assert_eq!(outpoint.to_string(), format!("{}:7", TXID_DISPLAY));
+        assert_eq!(outpoint.internal_txid_hex(), hex::encode(&internal));
+        assert_eq!(Outpoint::from_internal_bytes(&internal, 7).unwrap(), outpoint);
+        assert_eq!(Outpoint::parse(&outpoint.to_string()).unwrap(), outpoint);
+
+        // Serialized outpoints (what the sighash commits to) carry the internal order
+        let serialized = encode::serialize(&OutPoint::from(outpoint));
+        assert_eq!(serialized[..32], internal[..]);
+        assert_eq!(serialized[32..], 7u32.to_le_bytes());
+    }
+
+    #[test]
+    fn outpoint_rejects_bad_input() {
+        assert!(Outpoint::parse(TXID_DISPLAY).is_err());
+        assert!(Outpoint::parse(&format!("{}:x", TXID_DISPLAY)).is_err());
+        assert!(Outpoint::from_internal_bytes(&[0; 31], 0).is_err());
+    }
+
+    #[test]
+    fn every_input_carries_its_witness_utxo() {
+        let psbt = three_input_psbt();
+        let expected = [(outpoint(0xaa, 0), 50_000, p2tr(1)), (outpoint(0xbb, 3), 20_000, p2tr(2)), (outpoint(0xcc, 1), 7_000, p2tr(3))];
+
+        assert_eq!(psbt.inputs.len(), psbt.unsigned_tx.input.len());
+        for ((input, txin), (outpoint, sats, script)) in psbt.inputs.iter().zip(&psbt.unsigned_tx.input).zip(expected) {
+            assert_eq!(txin.previous_output, OutPoint::from(outpoint));
+            let witness_utxo = input.witness_utxo.as_ref().unwrap();
+            assert_eq!(witness_utxo.value.to_sat(), sats);
+            assert_eq!(witness_utxo.script_pubkey, script);
+        }
+    }
+
+    #[test]
+    fn transaction_layout() {
+        let tx = three_input_psbt().unsigned_tx;
+        assert_eq!(tx.version, Version::TWO);
+        assert_eq!(tx.lock_time, LockTime::ZERO);
+        assert!(tx.input.iter().all(|input| input.sequence == Sequence::MAX && input.witness.is_empty()));
+
+        let rbf = PsbtBuilder::new().input(outpoint(0xaa, 0), 1_000, p2tr(1)).output(p2tr(2), 500).rbf().build().unwrap();
+        assert_eq!(rbf.unsigned_tx.input[0].sequence, Sequence::ENABLE_RBF_NO_LOCKTIME);
+
+        let csv = PsbtBuilder::new()
+            .csv_input(outpoint(0xaa, 0), 1_000, p2tr(1), 1_008)
+            .input(outpoint(0xbb, 0), 1_000, p2tr(1))
+            .output(p2tr(2), 1_500)
+            .build()
+            .unwrap();
+        assert_eq!(csv.unsigned_tx.input[0].sequence, Sequence::from_height(1_008));
+        assert_eq!(csv.unsigned_tx.input[1].sequence, Sequence::MAX);
+
+        let op_return = PsbtBuilder::new().input(outpoint(0xaa, 0), 1_000, p2tr(1)).op_return(vec![0x6a, 0x5d]).build().unwrap();
+        assert!(op_return.unsigned_tx.output[0].script_pubkey.is_op_return());
+        assert_eq!(op_return.unsigned_tx.output[0].value, Amount::ZERO);
+
+        let v1 = PsbtBuilder::new().input(outpoint(0xaa, 0), 1_000, p2tr(1)).output(p2tr(2), 500).version(Version::ONE).build().unwrap();
+        assert_eq!(v1.unsigned_tx.version, Version::ONE);
+    }
+
+    #[test]
+    fn empty_transactions_are_rejected() {
+        assert!(PsbtBuilder::new().output(p2tr(1), 1_000).build().is_err());
+        assert!(PsbtBuilder::new().input(outpoint(0xaa, 0), 1_000, p2tr(1)).build().is_err());
+    }
+
+    #[test]
+    fn key_path_signature_verifies_against_tweaked_pool_key() {
+        let secp = Secp256k1::new();
+        let pool_key = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[0x42; 32]).unwrap());
+        let (internal_key, _) = pool_key.x_only_public_key();
+        let tweaked = pool_key.tap_tweak(&secp, None).to_keypair();
+        let (tweaked_key, _) = tweaked.x_only_public_key();
+
+        // The pool's P2TR output commits to the tweaked key, not the internal one
+        let pool_script = ScriptBuf::new_p2tr(&secp, internal_key, None);
+        assert_eq!(pool_script.as_bytes()[2..], tweaked_key.serialize());
+
+        let psbt = PsbtBuilder::new()
+            .input(outpoint(0xaa, 0), 50_000, p2tr(1))
+            .input(outpoint(0xbb, 3), 20_000, pool_script)
+            .output(p2tr(4), 60_000)
+            .output(p2tr(1), 9_000)
+            .build()
+            .unwrap();
+
+        let message = Message::from_digest(key_path_sighash(&psbt, 1));
+        let signature = secp.sign_schnorr_no_aux_rand(&message, &tweaked);
+        assert!(secp.verify_schnorr(&signature, &message, &tweaked_key).is_ok());
+        assert!(secp.verify_schnorr(&signature, &message, &internal_key).is_err());
+
+        // The signature is bound to input 1's sighash
+        let input_0 = Message::from_digest(key_path_sighash(&psbt, 0));
+        assert!(secp.verify_schnorr(&signature, &input_0, &tweaked_key).is_err());
+    }
+
+    #[test]
+    fn sighash_commits_to_every_witness_utxo() {
+        let psbt = three_input_psbt();
+        let signed = key_path_sighash(&psbt, 0);
+
+        // Another input's amount or script changes what input 0 signs
+        let mut amount = psbt.clone();
+        amount.inputs[2].witness_utxo.as_mut().unwrap().value = Amount::from_sat(7_001);
+        assert_ne!(key_path_sighash(&amount, 0), signed);
+
+        let mut script = psbt.clone();
+        script.inputs[1].witness_utxo.as_mut().unwrap().script_pubkey = p2tr(9);
+        assert_ne!(key_path_sighash(&script, 0), signed);
+    }
+}
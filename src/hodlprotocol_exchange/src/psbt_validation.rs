@@ -0,0 +1,545 @@
+// PSBT vs intention validation
+// Checks every input and output of a PSBT against the REE intention before the pool signs.
+// This runs ahead of the action-specific checks (validate_deposit / validate_redeem).
+use std::collections::HashSet;
+
+use ordinals::{Artifact, RuneId};
+use ree_types::{bitcoin::psbt::Psbt, bitcoin::ScriptBuf, CoinId, Intention, Txid};
+use std::str::FromStr;
+
+use crate::{address_script, allocate_blst_outputs, blst_coin_id, decipher_psbt_runestone, PoolConfig};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntentionError {
+    #[error("Invalid intention: {0}")]
+    Malformed(String),
+    #[error("pool_utxo_spent lists {spent}, which is not the pool's current UTXO {current}")]
+    UnknownPoolUtxo { spent: String, current: String },
+    #[error("PSBT does not spend pool UTXO {0} listed in pool_utxo_spent")]
+    PoolUtxoNotSpent(String),
+    #[error("PSBT input {vin} spends pool UTXO {outpoint}, which is not listed in pool_utxo_spent")]
+    UnlistedPoolInput { vin: usize, outpoint: String },
+    #[error("PSBT input {0} spends the pool but has no witness_utxo")]
+    MissingWitnessUtxo(usize),
+    #[error("PSBT input {vin} witness_utxo ({sats} sats) does not match pool UTXO {outpoint} ({expected} sats)")]
+    WitnessUtxoMismatch { vin: usize, outpoint: String, sats: u64, expected: u64 },
+    #[error("PSBT has {0} outputs paying the pool, expected exactly one")]
+    PoolOutputCount(usize),
+    #[error("pool_utxo_received lists {listed} UTXO(s), PSBT has {actual} pool output(s)")]
+    ReceivedUtxoCount { listed: usize, actual: usize },
+    #[error("pool_utxo_received {utxo} does not match the PSBT: {reason}")]
+    ReceivedUtxoMismatch { utxo: String, reason: String },
+    #[error("Pool balance drops from {before} to {after} sats but the intention only pays out {declared} sats")]
+    PoolValueLeak { before: u64, after: u64, declared: u64 },
+    #[error("PSBT pays {paid} sats to {address}, intention declares {declared} sats")]
+    UnderpaidRecipient { address: String, paid: u64, declared: u64 },
+    #[error("Runestone is a cenotaph (runes would be burned): {0}")]
+    Cenotaph(String),
+    #[error("Runestone edict moves unexpected rune {0}")]
+    UnexpectedEdict(String),
+    #[error("Runestone must not etch or mint runes")]
+    UnexpectedRuneOperation,
+    #[error("Runestone sends {amount} BLST to output {vout}, which is neither the pool nor a declared recipient")]
+    UnknownRuneRecipient { vout: usize, amount: u128 },
+}
+
+impl From<String> for IntentionError {
+    fn from(e: String) -> Self {
+        IntentionError::Malformed(e)
+    }
+}
+
+/// Validate that the PSBT does exactly what the intention says for this pool
+pub fn validate_psbt_against_intention(
+    pool_config: &PoolConfig,
+    psbt: &Psbt,
+    intention: &Intention,
+) -> Result<(), IntentionError> {
+    let pool_script = address_script(&pool_config.address)?;
+    let blst_id = blst_coin_id(pool_config)?;
+
+    let current_utxo = pool_config.states.last().and_then(|s| s.utxo.as_ref());
+    let current_outpoint = current_utxo.map(|utxo| format!("{}:{}", utxo.txid, utxo.vout));
+
+    // ---- Inputs: pool_utxo_spent must be the tracked UTXO and actually spent ----
+    for spent in intention.pool_utxo_spent.iter() {
+        if current_outpoint.as_ref() != Some(spent) {
+            return Err(IntentionError::UnknownPoolUtxo {
+                spent: spent.clone(),
+                current: current_outpoint.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    let mut pool_sats_in: u64 = 0;
+    let mut spent_found = HashSet::new();
+    for (vin, input) in psbt.unsigned_tx.input.iter().enumerate() {
+        let outpoint = input.previous_output.to_string();
+        if current_outpoint.as_ref() != Some(&outpoint) {
+            continue;
+        }
+        if !intention.pool_utxo_spent.contains(&outpoint) {
+            return Err(IntentionError::UnlistedPoolInput { vin, outpoint });
+        }
+
+        // ree_pool_sign commits to witness_utxo - it must be the pool's own output
+        let utxo = current_utxo.expect("current outpoint implies current UTXO");
+        let witness_utxo = psbt.inputs.get(vin)
+            .and_then(|i| i.witness_utxo.as_ref())
+            .ok_or(IntentionError::MissingWitnessUtxo(vin))?;
+        if witness_utxo.value.to_sat() != utxo.sats || witness_utxo.script_pubkey != pool_script {
+            return Err(IntentionError::WitnessUtxoMismatch {
+                vin,
+                outpoint,
+                sats: witness_utxo.value.to_sat(),
+                expected: utxo.sats,
+            });
+        }
+
+        pool_sats_in += utxo.sats;
+        spent_found.insert(outpoint);
+    }
+
+    for spent in intention.pool_utxo_spent.iter() {
+        if !spent_found.contains(spent) {
+            return Err(IntentionError::PoolUtxoNotSpent(spent.clone()));
+        }
+    }
+
+    // ---- Runestone: only BLST edicts, no etching/minting, no cenotaphs ----
+    let expected_rune = RuneId::from_str(&blst_id.to_string())
+        .map_err(|e| format!("Invalid rune ID {}: {:?}", blst_id, e))?;
+
+    match decipher_psbt_runestone(psbt)? {
+        Some(Artifact::Cenotaph(cenotaph)) => {
+            return Err(IntentionError::Cenotaph(format!("{:?}", cenotaph)));
+        }
+        Some(Artifact::Runestone(runestone)) => {
+            if runestone.etching.is_some() || runestone.mint.is_some() {
+                return Err(IntentionError::UnexpectedRuneOperation);
+            }
+            if let Some(edict) = runestone.edicts.iter().find(|edict| edict.id != expected_rune) {
+                return Err(IntentionError::UnexpectedEdict(edict.id.to_string()));
+            }
+        }
+        None => {}
+    }
+
+    // ---- Outputs: one pool output, matching pool_utxo_received ----
+    let pool_vouts: Vec<usize> = psbt.unsigned_tx.output.iter()
+        .enumerate()
+        .filter(|(_, output)| output.script_pubkey == pool_script)
+        .map(|(vout, _)| vout)
+        .collect();
+
+    if pool_vouts.len() != 1 {
+        return Err(IntentionError::PoolOutputCount(pool_vouts.len()));
+    }
+    if intention.pool_utxo_received.len() != pool_vouts.len() {
+        return Err(IntentionError::ReceivedUtxoCount {
+            listed: intention.pool_utxo_received.len(),
+            actual: pool_vouts.len(),
+        });
+    }
+
+    // BLST carried in: the pool's own BLST (if spent) plus BLST declared by the user
+    let pool_blst_in = current_utxo
+        .filter(|_| !spent_found.is_empty())
+        .map(|utxo| utxo.coins.value_of(&blst_id))
+        .unwrap_or_default();
+    let user_blst_in: u128 = intention.input_coins.iter()
+        .filter(|input| input.coin.id == blst_id)
+        .map(|input| input.coin.value)
+        .sum();
+    let allocation = allocate_blst_outputs(psbt, &blst_id, pool_blst_in + user_blst_in)?;
+
+    let txid: Txid = psbt.unsigned_tx.compute_txid().into();
+    let mut pool_sats_out: u64 = 0;
+    for received in intention.pool_utxo_received.iter() {
+        let label = format!("{}:{}", received.txid, received.vout);
+        let mismatch = |reason: String| IntentionError::ReceivedUtxoMismatch {
+            utxo: label.clone(),
+            reason,
+        };
+
+        if received.txid != txid {
+            return Err(mismatch(format!("txid differs from PSBT txid {}", txid)));
+        }
+        if !pool_vouts.contains(&(received.vout as usize)) {
+            return Err(mismatch("output does not pay the pool".to_string()));
+        }
+
+        let output = &psbt.unsigned_tx.output[received.vout as usize];
+        if output.value.to_sat() != received.sats {
+            return Err(mismatch(format!("PSBT output holds {} sats, listed {}", output.value.to_sat(), received.sats)));
+        }
+
+        let allocated_blst = allocation[received.vout as usize];
+        for coin in received.coins.iter() {
+            if coin.id != blst_id {
+                return Err(mismatch(format!("unexpected coin {}", coin.id)));
+            }
+        }
+        if received.coins.value_of(&blst_id) != allocated_blst {
+            return Err(mismatch(format!(
+                "runestone allocates {} BLST, listed {}",
+                allocated_blst,
+                received.coins.value_of(&blst_id)
+            )));
+        }
+
+        pool_sats_out += received.sats;
+    }
+
+    // ---- Value leaving the pool may only go to declared recipients ----
+    let mut recipients: Vec<ScriptBuf> = vec![];
+    let mut declared_btc_out: u64 = 0;
+    for output_coin in intention.output_coins.iter() {
+        let script = address_script(&output_coin.to)?;
+        if output_coin.coin.id == CoinId::btc() {
+            let declared = u64::try_from(output_coin.coin.value)
+                .map_err(|_| format!("BTC amount too large: {}", output_coin.coin.value))?;
+            let paid: u64 = psbt.unsigned_tx.output.iter()
+                .filter(|output| output.script_pubkey == script)
+                .map(|output| output.value.to_sat())
+                .sum();
+            if paid < declared {
+                return Err(IntentionError::UnderpaidRecipient {
+                    address: output_coin.to.clone(),
+                    paid,
+                    declared,
+                });
+            }
+            declared_btc_out += declared;
+        }
+        recipients.push(script);
+    }
+
+    if pool_sats_out + declared_btc_out < pool_sats_in {
+        return Err(IntentionError::PoolValueLeak {
+            before: pool_sats_in,
+            after: pool_sats_out,
+            declared: declared_btc_out,
+        });
+    }
+
+    for (vout, amount) in allocation.iter().enumerate() {
+        if *amount == 0 {
+            continue;
+        }
+        let script = &psbt.unsigned_tx.output[vout].script_pubkey;
+        let known = *script == pool_script || recipients.contains(script);
+        if !known {
+            return Err(IntentionError::UnknownRuneRecipient { vout, amount: *amount });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PoolState;
+    use ordinals::{Edict, Runestone};
+    use ree_types::bitcoin::{
+        absolute::LockTime, key::TweakedPublicKey, secp256k1::XOnlyPublicKey, transaction::Version,
+        Address, Amount, Network, OutPoint, Transaction, TxIn, TxOut,
+    };
+    use ree_types::{CoinBalance, CoinBalances, InputCoin, OutputCoin, Utxo};
+
+    const POOL_KEY: &str = "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+    const USER_KEY: &str = "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+    const STRANGER_KEY: &str = "e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13";
+    const POOL_TXID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+    const USER_TXID: &str = "2222222222222222222222222222222222222222222222222222222222222222";
+    const BLST_ID: &str = "840000:1";
+
+    const POOL_SATS: u64 = 100_000;
+    const POOL_BLST: u128 = 1_000;
+    const USER_BLST: u128 = 100;
+    const PAYOUT_SATS: u64 = 5_000;
+
+    fn address(key: &str) -> String {
+        let key = XOnlyPublicKey::from_str(key).unwrap();
+        Address::p2tr_tweaked(TweakedPublicKey::dangerous_assume_tweaked(key), Network::Testnet).to_string()
+    }
+
+    fn script(key: &str) -> ScriptBuf {
+        crate::address_script(&address(key)).unwrap()
+    }
+
+    fn blst(value: u128) -> CoinBalances {
+        let mut coins = CoinBalances::new();
+        coins.add_coin(&CoinBalance { id: CoinId::from_str(BLST_ID).unwrap(), value });
+        coins
+    }
+
+    fn pool_outpoint() -> OutPoint {
+        OutPoint { txid: POOL_TXID.parse().unwrap(), vout: 0 }
+    }
+
+    fn user_outpoint() -> OutPoint {
+        OutPoint { txid: USER_TXID.parse().unwrap(), vout: 1 }
+    }
+
+    fn pool_config() -> PoolConfig {
+        PoolConfig {
+            address: address(POOL_KEY),
+            blst_rune_id: Some(BLST_ID.to_string()),
+            states: vec![PoolState {
+                id: None,
+                nonce: 0,
+                utxo: Some(Utxo {
+                    txid: pool_outpoint().txid.into(),
+                    vout: 0,
+                    coins: blst(POOL_BLST),
+                    sats: POOL_SATS,
+                }),
+                accounting: Default::default(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn output(key: &str, sats: u64) -> TxOut {
+        TxOut { value: Amount::from_sat(sats), script_pubkey: script(key) }
+    }
+
+    fn runestone(runestone: Runestone) -> TxOut {
+        TxOut { value: Amount::ZERO, script_pubkey: ScriptBuf::from_bytes(runestone.encipher().to_bytes()) }
+    }
+
+    /// Unsigned PSBT spending `inputs` (outpoint, sats, owner key), each with its witness_utxo
+    fn psbt(inputs: &[(OutPoint, u64, &str)], outputs: Vec<TxOut>) -> Psbt {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: inputs.iter()
+                .map(|(outpoint, _, _)| TxIn { previous_output: *outpoint, ..Default::default() })
+                .collect(),
+            output: outputs,
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        for (input, (_, sats, key)) in psbt.inputs.iter_mut().zip(inputs) {
+            input.witness_utxo = Some(output(key, *sats));
+        }
+        psbt
+    }
+
+    /// Redemption: the user sends BLST to the pool and the pool pays out BTC
+    fn redeem_psbt(pool_sats_out: u64, user_sats: u64, extra: Vec<TxOut>) -> Psbt {
+        let mut outputs = vec![output(POOL_KEY, pool_sats_out), output(USER_KEY, user_sats)];
+        outputs.extend(extra);
+        psbt(&[(pool_outpoint(), POOL_SATS, POOL_KEY), (user_outpoint(), 10_000, USER_KEY)], outputs)
+    }
+
+    fn received(psbt: &Psbt, vout: u32, sats: u64, blst_value: u128) -> Utxo {
+        Utxo {
+            txid: psbt.unsigned_tx.compute_txid().into(),
+            vout,
+            coins: blst(blst_value),
+            sats,
+        }
+    }
+
+    fn redeem_intention(psbt: &Psbt) -> Intention {
+        Intention {
+            exchange_id: "hodlprotocol".to_string(),
+            action: "redeem".to_string(),
+            action_params: String::new(),
+            pool_address: address(POOL_KEY),
+            nonce: 0,
+            pool_utxo_spent: vec![pool_outpoint().to_string()],
+            pool_utxo_received: vec![received(psbt, 0, POOL_SATS - PAYOUT_SATS, POOL_BLST + USER_BLST)],
+            input_coins: vec![InputCoin {
+                from: address(USER_KEY),
+                coin: CoinBalance { id: CoinId::from_str(BLST_ID).unwrap(), value: USER_BLST },
+            }],
+            output_coins: vec![OutputCoin {
+                to: address(USER_KEY),
+                coin: CoinBalance { id: CoinId::btc(), value: PAYOUT_SATS as u128 },
+            }],
+        }
+    }
+
+    fn blst_rune() -> RuneId {
+        RuneId::from_str(BLST_ID).unwrap()
+    }
+
+    fn validate(psbt: &Psbt, intention: &Intention) -> Result<(), IntentionError> {
+        validate_psbt_against_intention(&pool_config(), psbt, intention)
+    }
+
+    #[test]
+    fn matching_redemption_passes() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        validate(&psbt, &redeem_intention(&psbt)).unwrap();
+    }
+
+    #[test]
+    fn pool_without_blst_rune_is_malformed() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        let mut pool_config = pool_config();
+        pool_config.blst_rune_id = None;
+        let result = validate_psbt_against_intention(&pool_config, &psbt, &redeem_intention(&psbt));
+        assert!(matches!(result, Err(IntentionError::Malformed(_))));
+    }
+
+    #[test]
+    fn stale_pool_utxo_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        let mut intention = redeem_intention(&psbt);
+        intention.pool_utxo_spent = vec![user_outpoint().to_string()];
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::UnknownPoolUtxo { .. })));
+    }
+
+    #[test]
+    fn listed_pool_utxo_must_be_spent() {
+        let psbt = psbt(&[(user_outpoint(), 10_000, USER_KEY)], vec![output(POOL_KEY, POOL_SATS)]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::PoolUtxoNotSpent(_))));
+    }
+
+    #[test]
+    fn unlisted_pool_input_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        let mut intention = redeem_intention(&psbt);
+        intention.pool_utxo_spent.clear();
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::UnlistedPoolInput { vin: 0, .. })));
+    }
+
+    #[test]
+    fn pool_input_needs_witness_utxo() {
+        let mut psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        psbt.inputs[0].witness_utxo = None;
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::MissingWitnessUtxo(0))));
+    }
+
+    #[test]
+    fn pool_witness_utxo_must_match_tracked_utxo() {
+        let psbt = psbt(
+            &[(pool_outpoint(), POOL_SATS + 1, POOL_KEY)],
+            vec![output(POOL_KEY, POOL_SATS - PAYOUT_SATS), output(USER_KEY, PAYOUT_SATS)],
+        );
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(
+            validate(&psbt, &intention),
+            Err(IntentionError::WitnessUtxoMismatch { vin: 0, sats, expected: POOL_SATS, .. }) if sats == POOL_SATS + 1
+        ));
+
+        // Right amount, but the witness_utxo claims a script other than the pool's
+        let mut psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        psbt.inputs[0].witness_utxo = Some(output(STRANGER_KEY, POOL_SATS));
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::WitnessUtxoMismatch { vin: 0, .. })));
+    }
+
+    #[test]
+    fn second_pool_output_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![output(POOL_KEY, 1_000)]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::PoolOutputCount(2))));
+    }
+
+    #[test]
+    fn pool_output_must_be_listed() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        let mut intention = redeem_intention(&psbt);
+        intention.pool_utxo_received.clear();
+        assert!(matches!(
+            validate(&psbt, &intention),
+            Err(IntentionError::ReceivedUtxoCount { listed: 0, actual: 1 })
+        ));
+    }
+
+    #[test]
+    fn received_utxo_must_match_pool_output() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![]);
+
+        let mut wrong_sats = redeem_intention(&psbt);
+        wrong_sats.pool_utxo_received[0].sats += 1;
+        assert!(matches!(validate(&psbt, &wrong_sats), Err(IntentionError::ReceivedUtxoMismatch { .. })));
+
+        let mut wrong_blst = redeem_intention(&psbt);
+        wrong_blst.pool_utxo_received[0].coins = blst(POOL_BLST);
+        assert!(matches!(validate(&psbt, &wrong_blst), Err(IntentionError::ReceivedUtxoMismatch { .. })));
+
+        let mut wrong_vout = redeem_intention(&psbt);
+        wrong_vout.pool_utxo_received[0].vout = 1;
+        assert!(matches!(validate(&psbt, &wrong_vout), Err(IntentionError::ReceivedUtxoMismatch { .. })));
+    }
+
+    #[test]
+    fn undeclared_pool_value_is_a_leak() {
+        // 10,000 sats leave the pool, only 5,000 are declared
+        let psbt = redeem_psbt(POOL_SATS - 2 * PAYOUT_SATS, PAYOUT_SATS, vec![]);
+        let mut intention = redeem_intention(&psbt);
+        intention.pool_utxo_received = vec![received(&psbt, 0, POOL_SATS - 2 * PAYOUT_SATS, POOL_BLST + USER_BLST)];
+        assert!(matches!(
+            validate(&psbt, &intention),
+            Err(IntentionError::PoolValueLeak { before: POOL_SATS, after, declared: PAYOUT_SATS })
+                if after == POOL_SATS - 2 * PAYOUT_SATS
+        ));
+    }
+
+    #[test]
+    fn underpaid_recipient_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS - 1, vec![]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(
+            validate(&psbt, &intention),
+            Err(IntentionError::UnderpaidRecipient { paid, declared: PAYOUT_SATS, .. }) if paid == PAYOUT_SATS - 1
+        ));
+    }
+
+    #[test]
+    fn cenotaph_is_rejected() {
+        // An edict to a missing output makes the runestone a cenotaph
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![runestone(Runestone {
+            edicts: vec![Edict { id: blst_rune(), amount: 1, output: 9 }],
+            ..Default::default()
+        })]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::Cenotaph(_))));
+    }
+
+    #[test]
+    fn edict_for_another_rune_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![runestone(Runestone {
+            edicts: vec![Edict { id: RuneId { block: 840_000, tx: 2 }, amount: 1, output: 1 }],
+            ..Default::default()
+        })]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::UnexpectedEdict(id)) if id == "840000:2"));
+    }
+
+    #[test]
+    fn mint_is_rejected() {
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![runestone(Runestone {
+            mint: Some(blst_rune()),
+            ..Default::default()
+        })]);
+        let intention = redeem_intention(&psbt);
+        assert!(matches!(validate(&psbt, &intention), Err(IntentionError::UnexpectedRuneOperation)));
+    }
+
+    #[test]
+    fn blst_to_undeclared_output_is_rejected() {
+        // Output 2 pays a stranger, and the runestone routes 10 BLST there
+        let psbt = redeem_psbt(POOL_SATS - PAYOUT_SATS, PAYOUT_SATS, vec![
+            output(STRANGER_KEY, 546),
+            runestone(Runestone {
+                edicts: vec![Edict { id: blst_rune(), amount: 10, output: 2 }],
+                ..Default::default()
+            }),
+        ]);
+        let mut intention = redeem_intention(&psbt);
+        intention.pool_utxo_received = vec![received(&psbt, 0, POOL_SATS - PAYOUT_SATS, POOL_BLST + USER_BLST - 10)];
+        assert!(matches!(
+            validate(&psbt, &intention),
+            Err(IntentionError::UnknownRuneRecipient { vout: 2, amount: 10 })
+        ));
+    }
+}
@@ -45,7 +45,7 @@ pub struct RuneBalance {
     pub symbol: Option<String>,
 }
 
-#[derive(CandidType, Deserialize)]
+#[derive(CandidType, Deserialize, Debug)]
 pub enum Error {
     MaxOutpointsExceeded,
 }
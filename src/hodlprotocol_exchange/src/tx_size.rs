@@ -0,0 +1,220 @@
+// Transaction weight / vsize calculator
+// Computes BIP141 weight from the shape of the transaction we are about to build (input witnesses,
+// output scripts, runestone length) so fees match what actually gets signed and relayed.
+
+/// P2TR scriptPubKey length: OP_1 <32-byte x-only key>
+pub const P2TR_SCRIPT_LEN: usize = 34;
+
+/// BIP340 Schnorr signature (SIGHASH_DEFAULT omits the sighash byte)
+pub const SCHNORR_SIG_LEN: usize = 64;
+
+// Non-witness bytes
+const TX_VERSION_LEN: usize = 4;
+const TX_LOCKTIME_LEN: usize = 4;
+const OUTPOINT_LEN: usize = 36;           // txid + vout
+const SEQUENCE_LEN: usize = 4;
+const OUTPUT_VALUE_LEN: usize = 8;
+
+// Witness bytes
+const SEGWIT_MARKER_FLAG_LEN: usize = 2;
+const WITNESS_SCALE_FACTOR: usize = 4;
+
+/// How an input will be satisfied once signed
+#[derive(Clone, Debug)]
+pub enum InputWitness {
+    /// Taproot key-path spend with SIGHASH_DEFAULT (what ree_pool_sign produces)
+    TaprootKeyPath,
+    /// Any other witness stack, given as the length of each item (e.g. Taproot script-path spends)
+    Items(Vec<usize>),
+}
+
+impl InputWitness {
+    fn item_lens(&self) -> Vec<usize> {
+        match self {
+            InputWitness::TaprootKeyPath => vec![SCHNORR_SIG_LEN],
+            InputWitness::Items(items) => items.clone(),
+        }
+    }
+
+    fn stack_size(&self) -> usize {
+        let items = self.item_lens();
+        compact_size_len(items.len()) + items.iter().map(|len| compact_size_len(*len) + len).sum::<usize>()
+    }
+
+    /// Weight one such input adds (excluding the segwit marker / flag and the input count)
+    pub fn weight(&self) -> u64 {
+        ((OUTPOINT_LEN + compact_size_len(0) + SEQUENCE_LEN) * WITNESS_SCALE_FACTOR + self.stack_size()) as u64
+    }
+}
+
+/// Shape of a transaction: one witness per input, one scriptPubKey length per output
+#[derive(Clone, Debug, Default)]
+pub struct TxShape {
+    inputs: Vec<InputWitness>,
+    output_script_lens: Vec<usize>,
+}
+
+impl TxShape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn input(mut self, witness: InputWitness) -> Self {
+        self.inputs.push(witness);
+        self
+    }
+
+    /// `count` Taproot key-path inputs signed by the pool / funding key
+    pub fn taproot_inputs(mut self, count: usize) -> Self {
+        self.inputs.extend(std::iter::repeat_n(InputWitness::TaprootKeyPath, count));
+        self
+    }
+
+    /// Output paying a scriptPubKey of `script_len` bytes (OP_RETURN scripts included)
+    pub fn output(mut self, script_len: usize) -> Self {
+        self.output_script_lens.push(script_len);
+        self
+    }
+
+    pub fn p2tr_output(self) -> Self {
+        self.output(P2TR_SCRIPT_LEN)
+    }
+
+    /// Serialized size without witness data
+    pub fn base_size(&self) -> usize {
+        let inputs: usize = self.inputs.len() * (OUTPOINT_LEN + compact_size_len(0) + SEQUENCE_LEN);
+        let outputs: usize = self.output_script_lens.iter()
+            .map(|len| OUTPUT_VALUE_LEN + compact_size_len(*len) + len)
+            .sum();
+
+        TX_VERSION_LEN
+            + compact_size_len(self.inputs.len())
+            + inputs
+            + compact_size_len(self.output_script_lens.len())
+            + outputs
+            + TX_LOCKTIME_LEN
+    }
+
+    /// Witness bytes, including the segwit marker and flag
+    pub fn witness_size(&self) -> usize {
+        let stacks: usize = self.inputs.iter().map(InputWitness::stack_size).sum();
+
+        if stacks == self.inputs.len() {
+            // Every stack is empty: serialized without marker / flag
+            return 0;
+        }
+        SEGWIT_MARKER_FLAG_LEN + stacks
+    }
+
+    /// BIP141 weight units
+    pub fn weight(&self) -> u64 {
+        (self.base_size() * WITNESS_SCALE_FACTOR + self.witness_size()) as u64
+    }
+
+    /// Virtual size in vbytes (weight / 4, rounded up)
+    pub fn vsize(&self) -> u64 {
+        self.weight().div_ceil(WITNESS_SCALE_FACTOR as u64)
+    }
+}
+
+/// Length of a Bitcoin CompactSize (varint) prefix for `n`
+fn compact_size_len(n: usize) -> usize {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psbt_builder::{Outpoint, PsbtBuilder};
+    use ree_types::bitcoin::{consensus::encode, ScriptBuf, Transaction, Witness};
+
+    /// Shape of a signed transaction, read back from its witnesses and output scripts
+    fn shape_of(tx: &Transaction) -> TxShape {
+        let shape = tx.input.iter().fold(TxShape::new(), |shape, input| {
+            shape.input(InputWitness::Items(input.witness.iter().map(|item| item.len()).collect()))
+        });
+        tx.output.iter().fold(shape, |shape, output| shape.output(output.script_pubkey.len()))
+    }
+
+    #[test]
+    fn matches_known_segwit_transactions() {
+        // (tx, weight) - mainnet transactions from rust-bitcoin's txin_txout_weight test
+        let known = [
+            // P2WPKH input → P2SH + P2WPKH outputs
+            ("020000000001018a763b78d3e17acea0625bf9e52b0dc1beb2241b2502185348ba8ff4a253176e0100000000ffffffff0280d725000000000017a914c07ed639bd46bf7087f2ae1dfde63b815a5f8b488767fda20300000000160014869ec8520fa2801c8a01bfdd2e82b19833cd0daf02473044022016243edad96b18c78b545325aaff80131689f681079fb107a67018cb7fb7830e02205520dae761d89728f73f1a7182157f6b5aecf653525855adb7ccb998c8e6143b012103b9489bde92afbcfa85129a82ffa512897105d1a27ad9806bded27e0532fc84e700000000", 565),
+            // P2WSH 2-of-3 input → P2PKH + P2WSH outputs
+            ("01000000000101a3ccad197118a2d4975fadc47b90eacfdeaf8268adfdf10ed3b4c3b7e1ad14530300000000ffffffff0200cc5501000000001976a91428ec6f21f4727bff84bb844e9697366feeb69f4d88aca2a5100d00000000220020701a8d401c84fb13e6baf169d59684e17abd9fa216c8cc5b9fc63d622ff8c58d04004730440220548f11130353b3a8f943d2f14260345fc7c20bde91704c9f1cbb5456355078cd0220383ed4ed39b079b618bcb279bbc1f2ca18cb028c4641cb522c9c5868c52a0dc20147304402203c332ecccb3181ca82c0600520ee51fee80d3b4a6ab110945e59475ec71e44ac0220679a11f3ca9993b04ccebda3c834876f353b065bb08f50076b25f5bb93c72ae1016952210375e00eb72e29da82b89367947f29ef34afb75e8654f6ea368e0acdfd92976b7c2103a1b26313f430c4b15bb1fdce663207659d8cac749a0e53d70eff01874496feff2103c96d495bfdd5ba4145e3e046fee45e84a8a48ad05bd8dbb395c011a32cf9f88053ae00000000", 766),
+            // P2TR key-path input with a SIGHASH_ALL byte → P2TR + P2WSH outputs
+            ("01000000000101b5cee87f1a60915c38bb0bc26aaf2b67be2b890bbc54bb4be1e40272e0d2fe0b0000000000ffffffff025529000000000000225120106daad8a5cb2e6fc74783714273bad554a148ca2d054e7a19250e9935366f3033760000000000002200205e6d83c44f57484fd2ef2a62b6d36cdcd6b3e06b661e33fd65588a28ad0dbe060141df9d1bfce71f90d68bf9e9461910b3716466bfe035c7dbabaa7791383af6c7ef405a3a1f481488a91d33cd90b098d13cb904323a3e215523aceaa04e1bb35cdb0100000000", 617),
+        ];
+
+        for (tx_hex, weight) in known {
+            let tx: Transaction = encode::deserialize_hex(tx_hex).unwrap();
+            let shape = shape_of(&tx);
+            assert_eq!(shape.weight(), weight);
+            assert_eq!(shape.base_size(), tx.base_size());
+            assert_eq!(shape.vsize(), tx.vsize() as u64);
+        }
+    }
+
+    #[test]
+    fn taproot_key_path_matches_signed_pool_transaction() {
+        // Pool-style transaction: SIGHASH_DEFAULT key-path inputs, P2TR outputs and a runestone
+        let op_return = vec![0x6a, 0x5d, 0x08, 0x00, 0xc0, 0xa2, 0x33, 0x01, 0x00, 0x01, 0x00];
+        let script = ScriptBuf::from_bytes([vec![0x51, 0x20], vec![7; 32]].concat());
+        for inputs in [1, 2, 5] {
+            let mut builder = PsbtBuilder::new();
+            for vout in 0..inputs {
+                builder = builder.input(Outpoint::from_display_hex(&"ab".repeat(32), vout).unwrap(), 10_000, script.clone());
+            }
+            let mut tx = builder
+                .output(script.clone(), 5_000)
+                .output(script.clone(), 4_000)
+                .op_return(op_return.clone())
+                .build()
+                .unwrap()
+                .unsigned_tx;
+            for input in tx.input.iter_mut() {
+                input.witness = Witness::from_slice(&[[0u8; SCHNORR_SIG_LEN]]);
+            }
+
+            let shape = TxShape::new()
+                .taproot_inputs(inputs as usize)
+                .p2tr_output()
+                .p2tr_output()
+                .output(op_return.len());
+            assert_eq!(shape.weight(), tx.weight().to_wu());
+            assert_eq!(shape.vsize(), tx.vsize() as u64);
+        }
+    }
+
+    #[test]
+    fn input_weight_is_the_marginal_weight() {
+        let one = TxShape::new().taproot_inputs(1).p2tr_output();
+        let two = TxShape::new().taproot_inputs(2).p2tr_output();
+        assert_eq!(two.weight() - one.weight(), InputWitness::TaprootKeyPath.weight());
+        assert_eq!(InputWitness::TaprootKeyPath.weight(), 230);  // 57.5 vB
+    }
+
+    #[test]
+    fn no_witness_means_no_marker() {
+        let shape = TxShape::new().input(InputWitness::Items(vec![])).p2tr_output();
+        assert_eq!(shape.witness_size(), 0);
+        assert_eq!(shape.weight(), shape.base_size() as u64 * 4);
+    }
+
+    #[test]
+    fn compact_size_boundaries() {
+        assert_eq!(compact_size_len(0xfc), 1);
+        assert_eq!(compact_size_len(0xfd), 3);
+        assert_eq!(compact_size_len(0xffff), 3);
+        assert_eq!(compact_size_len(0x1_0000), 5);
+        assert_eq!(compact_size_len(0xffff_ffff), 5);
+        assert_eq!(compact_size_len(0x1_0000_0000), 9);
+    }
+}